@@ -0,0 +1,159 @@
+//! Check a config file without starting the game.
+//!
+//! Usage: `jdsd_er_check_config [path/to/jdsd_er_practice_tool.toml]`
+//!
+//! Prints the commands, radial menu and indicators the practice tool would
+//! build from the file for each profile, followed by any hotkey conflicts.
+//! Exits with a non-zero status if the file doesn't parse.
+
+use std::fmt::{Display, Write};
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use practice_tool_config::{CfgCommand, CfgCommandEntry, Config, IndicatorType};
+
+fn list<T: Display>(values: &[T]) -> String {
+    values.iter().map(ToString::to_string).collect::<Vec<_>>().join(", ")
+}
+
+fn describe(command: &CfgCommand) -> String {
+    match command {
+        CfgCommand::Flag { flag, .. } => format!("Flag \"{}\"", flag.label),
+        CfgCommand::MultiFlag { flag, .. } => format!("Flag \"{}\"", flag.label),
+        CfgCommand::SpecialFlag { flag, .. } => format!("Flag \"{flag}\""),
+        CfgCommand::MultiFlagUser { flags, label, .. } => {
+            let names = flags.iter().map(|flag| flag.name.as_str()).collect::<Vec<_>>();
+            format!("Flags \"{label}\" ({})", list(&names))
+        },
+        CfgCommand::Label { label } => format!("Label \"{label}\""),
+        CfgCommand::SavefileManager { .. } => "Savefile manager".to_string(),
+        CfgCommand::ItemSpawner { .. } => "Item spawner".to_string(),
+        CfgCommand::Position { .. } => "Position".to_string(),
+        CfgCommand::NudgePosition { nudge, .. } => format!("Nudge position by {nudge}"),
+        CfgCommand::CycleSpeed { cycle_speed, .. } => {
+            format!("Cycle speed ({})", list(cycle_speed))
+        },
+        CfgCommand::CycleColor { cycle_color, .. } => {
+            format!("Cycle mesh color ({})", list(cycle_color))
+        },
+        CfgCommand::CharacterStats { .. } => "Character stats".to_string(),
+        CfgCommand::Runes { amount, .. } => format!("Add {amount} runes"),
+        CfgCommand::Target { track, .. } => format!("Target entity info (track {track})"),
+        CfgCommand::DamageLog { dps_window, .. } => {
+            format!("Damage log ({dps_window}s DPS window)")
+        },
+        CfgCommand::FrameAdvance { frames, .. } => {
+            format!("Frame advance ({frames} frames per step)")
+        },
+        CfgCommand::Warp { .. } => "Warp".to_string(),
+        CfgCommand::Group { label, .. } => format!("Group \"{label}\""),
+        CfgCommand::Quitout { .. } => "Quitout".to_string(),
+    }
+}
+
+fn write_commands(out: &mut String, commands: &[CfgCommandEntry], depth: usize) {
+    for entry in commands {
+        write!(out, "{:indent$}{}", "", describe(&entry.command), indent = depth * 2).ok();
+
+        let hotkeys = entry.command.hotkeys();
+        if let [(_, key)] = hotkeys.as_slice() {
+            write!(out, " [{key}]").ok();
+        } else if !hotkeys.is_empty() {
+            let hotkeys =
+                hotkeys.iter().map(|(label, key)| format!("{label}: {key}")).collect::<Vec<_>>();
+            write!(out, " [{}]", hotkeys.join(", ")).ok();
+        }
+        if entry.allow_shared {
+            write!(out, " (shared)").ok();
+        }
+        writeln!(out).ok();
+
+        if let CfgCommand::Group { commands, .. } = &entry.command {
+            write_commands(out, commands, depth + 1);
+        }
+    }
+}
+
+fn write_profile(out: &mut String, config: &Config) {
+    writeln!(out, "\nCommands:").ok();
+    write_commands(out, &config.commands, 1);
+
+    if !config.radial_menu.is_empty() {
+        writeln!(out, "\nRadial menu:").ok();
+        for entry in &config.radial_menu {
+            writeln!(out, "  {} [{}]", entry.label, entry.key).ok();
+        }
+    }
+
+    writeln!(out, "\nIndicators:").ok();
+    for indicator in &config.settings.indicators {
+        let name = match &indicator.indicator {
+            IndicatorType::Custom(custom) => format!("Custom \"{}\"", custom.label),
+            indicator => format!("{indicator:?}"),
+        };
+        let state = if indicator.enabled { "on" } else { "off" };
+        writeln!(out, "  {name} ({state})").ok();
+    }
+}
+
+fn summary(config: &Config) -> String {
+    let mut out = String::new();
+
+    writeln!(out, "Display: {}", config.settings.display).ok();
+    if let Some(hide) = config.settings.hide {
+        writeln!(out, "Hide: {hide}").ok();
+    }
+    for include in &config.includes {
+        writeln!(out, "Includes: {}", include.display()).ok();
+    }
+
+    if config.profiles.is_empty() {
+        write_profile(&mut out, config);
+        return out;
+    }
+
+    if let Some(cycle_profile) = config.settings.cycle_profile {
+        writeln!(out, "Next profile: {cycle_profile}").ok();
+    }
+    for (index, name) in config.profile_names().into_iter().enumerate() {
+        let active = if index == config.active_profile() { " (active)" } else { "" };
+        writeln!(out, "\n== Profile \"{name}\"{active} ==").ok();
+        write_profile(&mut out, &config.with_profile(index));
+    }
+
+    out
+}
+
+fn main() -> ExitCode {
+    let path = std::env::args_os()
+        .nth(1)
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from("jdsd_er_practice_tool.toml"));
+
+    let config = match Config::load(&path) {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("{e}");
+            return ExitCode::FAILURE;
+        },
+    };
+
+    print!("{}", summary(&config));
+
+    for (index, name) in config.profile_names().into_iter().enumerate() {
+        let conflicts = config.with_profile(index).hotkey_conflicts();
+        if !conflicts.is_empty() {
+            eprintln!();
+        }
+        for conflict in conflicts {
+            if config.profiles.is_empty() {
+                eprintln!("Warning: {conflict}");
+            } else {
+                eprintln!("Warning: profile \"{name}\": {conflict}");
+            }
+        }
+    }
+
+    println!("\n{} is valid.", path.display());
+    ExitCode::SUCCESS
+}
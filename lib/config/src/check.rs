@@ -0,0 +1,563 @@
+//! Config validation with line numbers and suggestions.
+//!
+//! Commands are deserialized as an untagged enum, so serde can only report
+//! that a command matched none of its shapes. This pass walks the document
+//! first and points at the offending line instead, suggesting the closest
+//! valid name for misspelled keys, flags and indicators.
+
+use std::fmt::Write;
+use std::ops::Range;
+
+use serde_path_to_error::{Path, Segment};
+use toml_edit::{ImDocument, Item, TableLike, Value};
+
+use crate::include::INCLUDE_KEYS;
+use crate::{DEFAULT_PROFILE, FLAG_NAMES, INDICATOR_NAMES, MULTIFLAG_NAMES, SPECIAL_FLAG_NAMES};
+
+/// Keys of a `[[profiles]]` table.
+pub(crate) const PROFILE_KEYS: &[&str] = &["name", "commands", "radial-menu", "indicators"];
+
+/// A command shape: the key that identifies it and the other keys it takes.
+pub(crate) struct Shape {
+    pub(crate) key: &'static str,
+    pub(crate) required: &'static [&'static str],
+    pub(crate) optional: &'static [&'static str],
+    pub(crate) example: &'static str,
+}
+
+// Checked in order, so `flags` comes before the plain `label`.
+pub(crate) const SHAPES: &[Shape] = &[
+    Shape {
+        key: "savefile_manager",
+        required: &[],
+        optional: &[],
+        example: r#"{ savefile_manager = "ctrl+o" }"#,
+    },
+    Shape {
+        key: "item_spawner",
+        required: &[],
+        optional: &[],
+        example: r#"{ item_spawner = "ctrl+u" }"#,
+    },
+    Shape {
+        key: "flag",
+        required: &[],
+        optional: &["hotkey"],
+        example: r#"{ flag = "no_damage", hotkey = "1" }"#,
+    },
+    Shape {
+        key: "flags",
+        required: &["label"],
+        optional: &["hotkey"],
+        example: r#"{ flags = ["collision", "torrent_collision"], label = "No Collision" }"#,
+    },
+    Shape {
+        key: "position",
+        required: &[],
+        optional: &["save"],
+        example: r#"{ position = "h", save = "rshift+h" }"#,
+    },
+    Shape {
+        key: "nudge",
+        required: &[],
+        optional: &["nudge_up", "nudge_down"],
+        example: r#"{ nudge = 1.0, nudge_up = "[", nudge_down = "]" }"#,
+    },
+    Shape {
+        key: "cycle_speed",
+        required: &[],
+        optional: &["hotkey"],
+        example: r#"{ cycle_speed = [0.5, 1.0, 2.0], hotkey = "8" }"#,
+    },
+    Shape {
+        key: "cycle_color",
+        required: &[],
+        optional: &["hotkey"],
+        example: r#"{ cycle_color = [0, 1, 2, 3, 4, 5] }"#,
+    },
+    Shape {
+        key: "character_stats",
+        required: &[],
+        optional: &[],
+        example: r#"{ character_stats = true }"#,
+    },
+    Shape {
+        key: "runes",
+        required: &[],
+        optional: &["hotkey"],
+        example: r#"{ runes = 10000, hotkey = "9" }"#,
+    },
+    Shape {
+        key: "target",
+        required: &[],
+        optional: &["pin", "track"],
+        example: r#"{ target = "ctrl+n", pin = "ctrl+m", track = 4 }"#,
+    },
+    Shape {
+        key: "damage_log",
+        required: &[],
+        optional: &["dps_window"],
+        example: r#"{ damage_log = true, dps_window = 5.0 }"#,
+    },
+    Shape {
+        key: "frame_advance",
+        required: &[],
+        optional: &["step", "frames"],
+        example: r#"{ frame_advance = "ctrl+f", step = "ctrl+g", frames = 1 }"#,
+    },
+    Shape { key: "warp", required: &[], optional: &[], example: r#"{ warp = true }"# },
+    Shape {
+        key: "group",
+        required: &[],
+        optional: &["commands"],
+        example: r#"{ group = "Name", commands = [ ... ] }"#,
+    },
+    Shape { key: "quitout", required: &[], optional: &[], example: r#"{ quitout = "p" }"# },
+    Shape { key: "label", required: &[], optional: &[], example: r#"{ label = "Text" }"# },
+];
+
+/// Check the config file, returning every problem found, one per line.
+#[cfg(test)]
+pub(crate) fn check(cfg: &str) -> Result<(), String> {
+    let doc = cfg.parse::<toml::Value>().ok();
+    let groups = doc.as_ref().and_then(|doc| doc.get("groups")?.as_table());
+    let groups = groups.map(|groups| groups.keys().cloned().collect::<Vec<_>>());
+    check_file(cfg, None, false, &groups.unwrap_or_default())
+}
+
+/// Check one of the files making up the config. `file` names it in errors,
+/// and `groups` are the groups defined across all files.
+pub(crate) fn check_file(
+    cfg: &str,
+    file: Option<&str>,
+    included: bool,
+    groups: &[String],
+) -> Result<(), String> {
+    let doc = ImDocument::parse(cfg).map_err(|e| match file {
+        Some(file) => format!("{file}: {e}"),
+        None => e.to_string(),
+    })?;
+    let mut checker = Checker { cfg, file, groups, errors: Vec::new() };
+
+    if included {
+        for (key, _) in doc.iter() {
+            if !INCLUDE_KEYS.contains(&key) {
+                let span = doc.key(key).and_then(|key| key.span());
+                checker.error(
+                    span,
+                    format!(
+                        "`{key}` can't be set in an included file, only `include`, `groups`, \
+                         `commands` and `radial-menu`"
+                    ),
+                );
+            }
+        }
+    }
+
+    if let Some(include) = doc.get("include") {
+        let paths = include.as_array().map(|paths| paths.iter().all(|path| path.is_str()));
+        if paths != Some(true) {
+            checker.error(include.span(), "`include` must be an array of paths".to_string());
+        }
+    }
+
+    if let Some(commands) = doc.get("commands") {
+        checker.check_commands(commands);
+    }
+
+    if let Some(definitions) = doc.get("groups") {
+        match definitions.as_table_like() {
+            Some(definitions) => {
+                for (_, commands) in definitions.iter() {
+                    checker.check_commands(commands);
+                }
+            },
+            None => checker.error(definitions.span(), "`groups` must be a table".to_string()),
+        }
+    }
+
+    if let Some(indicators) = doc.get("settings").and_then(|settings| settings.get("indicators")) {
+        checker.check_indicators(indicators);
+    }
+
+    let profiles = doc.get("profiles").map(|profiles| checker.check_profiles(profiles));
+    let profile = doc.get("settings").and_then(|settings| settings.get("profile"));
+    if let Some((name, span)) = profile.and_then(|name| Some((name.as_str()?, name.span()))) {
+        let profiles = profiles.unwrap_or_default();
+        let profiles = profiles.iter().map(String::as_str).collect::<Vec<_>>();
+        checker.check_name(name, span, "profile", &[&[DEFAULT_PROFILE], &profiles]);
+    }
+
+    if checker.errors.is_empty() {
+        Ok(())
+    } else {
+        Err(checker.errors.join("\n"))
+    }
+}
+
+/// Line and column of the value a deserialization error points at.
+pub(crate) fn locate(cfg: &str, path: &Path) -> Option<(usize, usize)> {
+    let doc = ImDocument::parse(cfg).ok()?;
+    let mut item = doc.as_item();
+    let mut span = None;
+
+    for segment in path.iter() {
+        item = match segment {
+            Segment::Seq { index } => item.get(*index)?,
+            Segment::Map { key } => item.get(key.as_str())?,
+            Segment::Enum { .. } | Segment::Unknown => break,
+        };
+        span = item.span().or(span);
+    }
+
+    span.map(|span| line_col(cfg, span.start))
+}
+
+fn line_col(cfg: &str, offset: usize) -> (usize, usize) {
+    let before = &cfg[..offset.min(cfg.len())];
+    let line = before.matches('\n').count() + 1;
+    let column = before.rsplit('\n').next().unwrap_or_default().chars().count() + 1;
+    (line, column)
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b = b.chars().collect::<Vec<_>>();
+    let mut row = (0..=b.len()).collect::<Vec<_>>();
+
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, &cb) in b.iter().enumerate() {
+            let above = row[j + 1];
+            row[j + 1] = if ca == cb { diagonal } else { 1 + diagonal.min(above).min(row[j]) };
+            diagonal = above;
+        }
+    }
+
+    row[b.len()]
+}
+
+/// The candidate closest to `name`, if it's close enough to be a typo.
+fn closest<'a>(name: &str, candidates: impl IntoIterator<Item = &'a str>) -> Option<&'a str> {
+    let max_distance = name.chars().count() / 3 + 1;
+    candidates
+        .into_iter()
+        .map(|candidate| (edit_distance(name, candidate), candidate))
+        .filter(|&(distance, _)| distance <= max_distance)
+        .min_by_key(|&(distance, _)| distance)
+        .map(|(_, candidate)| candidate)
+}
+
+fn did_you_mean(suggestion: Option<&str>, quote: &str) -> String {
+    match suggestion {
+        Some(suggestion) => format!(", did you mean {quote}{suggestion}{quote}?"),
+        None => String::new(),
+    }
+}
+
+struct Checker<'a> {
+    cfg: &'a str,
+    file: Option<&'a str>,
+    groups: &'a [String],
+    errors: Vec<String>,
+}
+
+impl Checker<'_> {
+    fn error(&mut self, span: Option<Range<usize>>, message: String) {
+        let file = self.file.map(|file| format!(" in {file}")).unwrap_or_default();
+        self.errors.push(match span {
+            Some(span) => {
+                let (line, column) = line_col(self.cfg, span.start);
+                format!("TOML config error{file} at line {line}, column {column}: {message}")
+            },
+            None => format!("TOML config error{file}: {message}"),
+        });
+    }
+
+    fn check_commands(&mut self, commands: &Item) {
+        match commands {
+            Item::Value(Value::Array(commands)) => {
+                for command in commands {
+                    match command.as_inline_table() {
+                        Some(table) => self.check_command(table, command.span()),
+                        None => self.error(command.span(), "commands must be tables".to_string()),
+                    }
+                }
+            },
+            Item::ArrayOfTables(commands) => {
+                for command in commands {
+                    self.check_command(command, command.span());
+                }
+            },
+            _ => self.error(commands.span(), "`commands` must be an array".to_string()),
+        }
+    }
+
+    fn check_command(&mut self, table: &dyn TableLike, span: Option<Range<usize>>) {
+        let Some(shape) = SHAPES.iter().find(|shape| table.contains_key(shape.key)) else {
+            let suggestion = table
+                .iter()
+                .find_map(|(key, _)| closest(key, SHAPES.iter().map(|shape| shape.key)));
+            let mut message =
+                format!("unrecognized command{}\nExpected one of:", did_you_mean(suggestion, "`"));
+            for shape in SHAPES {
+                write!(message, "\n  {}", shape.example).ok();
+            }
+            self.error(span, message);
+            return;
+        };
+
+        for key in shape.required {
+            if !table.contains_key(key) {
+                self.error(span.clone(), format!("`{}` commands need a `{key}`", shape.key));
+            }
+        }
+
+        let accepted = || {
+            [shape.key, "allow_shared"]
+                .into_iter()
+                .chain(shape.required.iter().copied())
+                .chain(shape.optional.iter().copied())
+        };
+        for (key, _) in table.iter() {
+            if !accepted().any(|accepted| accepted == key) {
+                let key_span = table.key(key).and_then(|key| key.span());
+                let suggestion = did_you_mean(closest(key, accepted()), "`");
+                self.error(
+                    key_span,
+                    format!("unknown key `{key}` in `{}` command{suggestion}", shape.key),
+                );
+            }
+        }
+
+        let value = table.get(shape.key).and_then(Item::as_value);
+        match (shape.key, value) {
+            ("flag", Some(Value::String(flag))) => {
+                let names = [FLAG_NAMES, MULTIFLAG_NAMES, SPECIAL_FLAG_NAMES];
+                self.check_name(flag.value(), flag.span(), "flag", &names);
+            },
+            ("flags", Some(Value::Array(flags))) => {
+                for flag in flags {
+                    if let Some(name) = flag.as_str() {
+                        self.check_name(name, flag.span(), "flag", &[FLAG_NAMES]);
+                    }
+                }
+            },
+            ("group", value) => match (table.get("commands"), value) {
+                (Some(commands), _) => self.check_commands(commands),
+                (None, Some(Value::String(name))) => {
+                    let groups = self.groups.iter().map(String::as_str).collect::<Vec<_>>();
+                    self.check_name(name.value(), name.span(), "group", &[&groups]);
+                },
+                (None, _) => {},
+            },
+            _ => {},
+        }
+    }
+
+    fn check_indicators(&mut self, indicators: &Item) {
+        let Some(indicators) = indicators.as_array() else {
+            return;
+        };
+
+        for indicator in indicators {
+            let name = indicator
+                .as_inline_table()
+                .and_then(|indicator| indicator.get("indicator"))
+                .and_then(|name| Some((name.as_str()?, name.span())));
+            if let Some((name, span)) = name {
+                self.check_name(name, span, "indicator", &[INDICATOR_NAMES]);
+            }
+        }
+    }
+
+    /// Checks each profile, returning their names.
+    fn check_profiles(&mut self, profiles: &Item) -> Vec<String> {
+        let profiles: Vec<(&dyn TableLike, Option<Range<usize>>)> = match profiles {
+            Item::ArrayOfTables(profiles) => {
+                profiles.iter().map(|profile| (profile as &dyn TableLike, profile.span())).collect()
+            },
+            Item::Value(Value::Array(profiles)) => profiles
+                .iter()
+                .filter_map(|profile| Some((profile.as_inline_table()? as _, profile.span())))
+                .collect(),
+            _ => {
+                self.error(profiles.span(), "`profiles` must be an array of tables".to_string());
+                return Vec::new();
+            },
+        };
+
+        let mut names = Vec::new();
+        for (profile, span) in profiles {
+            for (key, _) in profile.iter() {
+                if !PROFILE_KEYS.contains(&key) {
+                    let key_span = profile.key(key).and_then(|key| key.span());
+                    let suggestion = did_you_mean(closest(key, PROFILE_KEYS.iter().copied()), "`");
+                    self.error(key_span, format!("unknown key `{key}` in profile{suggestion}"));
+                }
+            }
+
+            match profile.get("name").and_then(|name| Some((name.as_str()?, name.span()))) {
+                Some((name, name_span)) => {
+                    if name == DEFAULT_PROFILE || names.iter().any(|taken| taken == name) {
+                        self.error(name_span, format!("profile name \"{name}\" is already taken"));
+                    }
+                    names.push(name.to_string());
+                },
+                None => self.error(span, "profiles need a `name`".to_string()),
+            }
+
+            if let Some(commands) = profile.get("commands") {
+                self.check_commands(commands);
+            }
+            if let Some(indicators) = profile.get("indicators") {
+                self.check_indicators(indicators);
+            }
+        }
+
+        names
+    }
+
+    fn check_name(
+        &mut self,
+        name: &str,
+        span: Option<Range<usize>>,
+        what: &str,
+        names: &[&[&str]],
+    ) {
+        let valid = names.iter().flat_map(|names| names.iter().copied());
+        if !valid.clone().any(|valid| valid == name) {
+            let suggestion = did_you_mean(closest(name, valid), "\"");
+            self.error(span, format!("\"{name}\" is not a valid {what}{suggestion}"));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::Deserialize;
+
+    use super::*;
+
+    fn check_fixture(fixture: &str) -> String {
+        check(fixture).expect_err("fixture should not pass validation")
+    }
+
+    #[test]
+    fn test_shipped_config() {
+        check(include_str!("../../../jdsd_er_practice_tool.toml")).unwrap();
+    }
+
+    #[test]
+    fn test_flag_typo() {
+        let err = check_fixture(include_str!("../tests/fixtures/flag_typo.toml"));
+        assert_eq!(
+            err,
+            "TOML config error at line 3, column 12: \"no_damge\" is not a valid flag, did you \
+             mean \"no_damage\"?"
+        );
+    }
+
+    #[test]
+    fn test_flags_typo() {
+        let err = check_fixture(include_str!("../tests/fixtures/flags_typo.toml"));
+        assert_eq!(
+            err,
+            "TOML config error at line 4, column 27: \"torrent_colision\" is not a valid flag, \
+             did you mean \"torrent_collision\"?\nTOML config error at line 5, column 3: `flags` \
+             commands need a `label`"
+        );
+    }
+
+    #[test]
+    fn test_unknown_command() {
+        let err = check_fixture(include_str!("../tests/fixtures/unknown_command.toml"));
+        assert!(err.starts_with(
+            "TOML config error at line 4, column 3: unrecognized command, did you mean \
+             `runes`?\nExpected one of:\n  { savefile_manager = \"ctrl+o\" }\n"
+        ));
+        assert!(err.contains("\n  { flag = \"no_damage\", hotkey = \"1\" }\n"));
+    }
+
+    #[test]
+    fn test_unknown_key() {
+        let err = check_fixture(include_str!("../tests/fixtures/unknown_key.toml"));
+        assert_eq!(
+            err,
+            "TOML config error at line 3, column 34: unknown key `hotky` in `flag` command, did \
+             you mean `hotkey`?"
+        );
+    }
+
+    #[test]
+    fn test_nested_group() {
+        let err = check_fixture(include_str!("../tests/fixtures/nested_group.toml"));
+        assert_eq!(
+            err,
+            "TOML config error at line 5, column 14: \"hitbox_hihg\" is not a valid flag, did you \
+             mean \"hitbox_high\"?\nTOML config error at line 7, column 14: \"sound\" is not a \
+             valid flag"
+        );
+    }
+
+    #[test]
+    fn test_indicator_typo() {
+        let err = check_fixture(include_str!("../tests/fixtures/indicator_typo.toml"));
+        assert_eq!(
+            err,
+            "TOML config error at line 6, column 17: \"igtt\" is not a valid indicator, did you \
+             mean \"igt\"?"
+        );
+    }
+
+    #[test]
+    fn test_profiles() {
+        let err = check_fixture(include_str!("../tests/fixtures/profiles.toml"));
+        assert_eq!(err.lines().collect::<Vec<_>>(), [
+            "TOML config error at line 11, column 1: unknown key `comands` in profile, did you \
+             mean `commands`?",
+            "TOML config error at line 14, column 8: profile name \"Bosses\" is already taken",
+            "TOML config error at line 15, column 29: \"igtt\" is not a valid indicator, did you \
+             mean \"igt\"?",
+            "TOML config error at line 17, column 1: profiles need a `name`",
+            "TOML config error at line 7, column 11: \"Boses\" is not a valid profile, did you \
+             mean \"Bosses\"?",
+        ]);
+    }
+
+    #[test]
+    fn test_syntax_error() {
+        let err = check_fixture(include_str!("../tests/fixtures/syntax_error.toml"));
+        assert!(err.starts_with("TOML parse error at line 3, column"), "{err}");
+    }
+
+    #[test]
+    fn test_locate() {
+        #[derive(Deserialize)]
+        #[allow(dead_code)]
+        struct Settings {
+            display: String,
+            frames: u32,
+        }
+
+        #[derive(Deserialize)]
+        #[allow(dead_code)]
+        struct Config {
+            settings: Settings,
+        }
+
+        let cfg = "[settings]\ndisplay = \"0\"\nframes = \"one\"\n";
+        let de = &mut toml::de::Deserializer::new(cfg);
+        let err = serde_path_to_error::deserialize::<_, Config>(de).err().unwrap();
+        assert_eq!(locate(cfg, err.path()), Some((3, 10)));
+    }
+
+    #[test]
+    fn test_closest() {
+        assert_eq!(closest("no_damge", FLAG_NAMES.iter().copied()), Some("no_damage"));
+        assert_eq!(closest("hotky", ["hotkey", "label"]), Some("hotkey"));
+        assert_eq!(closest("xyz", ["hotkey", "label"]), None);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        assert_eq!(edit_distance("", "abc"), 3);
+    }
+}
@@ -0,0 +1,167 @@
+//! Indicators backed by user-defined pointer chains.
+//!
+//! An expression is a `+`-separated list of terms, one per level of the chain,
+//! exactly like the arguments of `pointer_chain!`: the first term is the base
+//! address and every following one is the offset added after dereferencing.
+//! Terms are decimal or `0x` hexadecimal literals, or the name of a base
+//! address or `WorldChrMan` offset, e.g.
+//! `world_chr_man+player_ins+0x190+0+0x154`.
+
+use std::str::FromStr;
+
+use serde::Deserialize;
+
+/// Names that can be used as terms of an expression. The practice tool maps
+/// each of them to an address once the game's pointers are known.
+pub const SYMBOL_NAMES: &[&str] = &[
+    "bullet_man",
+    "chr_dbg_flags",
+    "cs_flipper",
+    "cs_lua_event_manager",
+    "cs_menu_man",
+    "cs_menu_man_imp",
+    "cs_net_man",
+    "cs_regulation_manager",
+    "cs_session_manager",
+    "damage_ctrl",
+    "field_area",
+    "game_data_man",
+    "game_man",
+    "global_pos",
+    "group_mask",
+    "hit_ins",
+    "map_item_man",
+    "msg_repository",
+    "solo_param_repository",
+    "world_chr_man",
+    "world_chr_man_dbg",
+    "world_chr_man_imp",
+    "current_target",
+    "base_fps",
+    "base_anim",
+    "net_players_ins",
+    "player_ins",
+    "torrent_enemy_ins",
+];
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Term {
+    Literal(usize),
+    Symbol(&'static str),
+}
+
+impl FromStr for Term {
+    type Err = String;
+
+    fn from_str(term: &str) -> Result<Self, Self::Err> {
+        let term = term.trim();
+
+        let literal = match term.strip_prefix("0x").or_else(|| term.strip_prefix("0X")) {
+            Some(hex) => usize::from_str_radix(hex, 16).ok(),
+            None => term.parse().ok(),
+        };
+        if let Some(literal) = literal {
+            return Ok(Term::Literal(literal));
+        }
+
+        SYMBOL_NAMES
+            .iter()
+            .find(|&&name| name == term)
+            .map(|&name| Term::Symbol(name))
+            .ok_or_else(|| format!("Unknown term {term:?} in custom indicator expression"))
+    }
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ValueType {
+    U8,
+    U16,
+    U32,
+    U64,
+    I8,
+    I16,
+    I32,
+    I64,
+    F32,
+    F64,
+}
+
+/// A custom indicator as written in the config file.
+#[derive(Debug, Clone)]
+pub struct CustomIndicatorSpec {
+    pub label: String,
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+    pub terms: Vec<Term>,
+    pub value_type: ValueType,
+}
+
+impl CustomIndicatorSpec {
+    pub fn new(
+        label: String,
+        expr: &str,
+        value_type: ValueType,
+        min: Option<f64>,
+        max: Option<f64>,
+    ) -> Result<Self, String> {
+        let terms = expr.split('+').map(Term::from_str).collect::<Result<Vec<_>, _>>()?;
+        Ok(CustomIndicatorSpec { label, min, max, terms, value_type })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_term() {
+        assert_eq!("0x190".parse(), Ok(Term::Literal(0x190)));
+        assert_eq!("0X1a".parse(), Ok(Term::Literal(0x1a)));
+        assert_eq!(" 42 ".parse(), Ok(Term::Literal(42)));
+        assert_eq!("0".parse(), Ok(Term::Literal(0)));
+        assert_eq!("world_chr_man".parse(), Ok(Term::Symbol("world_chr_man")));
+        assert_eq!(" player_ins".parse(), Ok(Term::Symbol("player_ins")));
+
+        assert_eq!(
+            "0xzz".parse::<Term>(),
+            Err("Unknown term \"0xzz\" in custom indicator expression".to_string())
+        );
+        assert!("World_Chr_Man".parse::<Term>().is_err());
+        assert!("-1".parse::<Term>().is_err());
+        assert!("".parse::<Term>().is_err());
+    }
+
+    #[test]
+    fn test_spec() {
+        let spec = CustomIndicatorSpec::new(
+            "HP".to_string(),
+            "world_chr_man + player_ins+0x190+0+0x138",
+            ValueType::U32,
+            Some(0.),
+            None,
+        )
+        .unwrap();
+        assert_eq!(spec.terms, [
+            Term::Symbol("world_chr_man"),
+            Term::Symbol("player_ins"),
+            Term::Literal(0x190),
+            Term::Literal(0),
+            Term::Literal(0x138),
+        ]);
+        assert_eq!((spec.label.as_str(), spec.min, spec.max), ("HP", Some(0.), None));
+
+        let spec = CustomIndicatorSpec::new("".to_string(), "0x7ff000", ValueType::F32, None, None);
+        assert_eq!(spec.unwrap().terms, [Term::Literal(0x7ff000)]);
+
+        let err = |expr| {
+            CustomIndicatorSpec::new("".to_string(), expr, ValueType::U8, None, None).unwrap_err()
+        };
+        assert_eq!(
+            err("world_chr_man+plyer_ins"),
+            "Unknown term \"plyer_ins\" in custom indicator expression"
+        );
+        assert_eq!(err("world_chr_man++0x10"), "Unknown term \"\" in custom indicator expression");
+        assert_eq!(err(""), "Unknown term \"\" in custom indicator expression");
+    }
+}
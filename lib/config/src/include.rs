@@ -0,0 +1,351 @@
+//! Configs split across several files, and groups of commands shared by name.
+//!
+//! The main file can list other files in `include`, relative to itself.
+//! Included files add their `commands` and `radial-menu` entries after the
+//! including file's own, and can include further files; each file is read
+//! once. Any file can define groups under `[groups]`, which commands use as
+//! `{ group = "Name" }`.
+//!
+//! Every file is checked and deserialized on its own first, so errors point
+//! at the file and line they come from. The files are then merged and group
+//! references expanded before building the [`Config`].
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+use toml::Value;
+
+use crate::{check, CfgCommandEntry, Config, Profile, RadialMenu, Settings};
+
+/// Top-level keys an included file can set.
+pub(crate) const INCLUDE_KEYS: &[&str] = &["include", "groups", "commands", "radial-menu"];
+
+/// One of the files making up the config.
+pub(crate) struct Source {
+    /// Name shown in errors, relative to the main file's directory.
+    pub(crate) name: Option<String>,
+    pub(crate) path: Option<PathBuf>,
+    pub(crate) content: String,
+    pub(crate) included: bool,
+}
+
+impl Source {
+    fn name(&self) -> &str {
+        self.name.as_deref().unwrap_or("the config file")
+    }
+
+    /// Where errors in this file are, when the config has several files.
+    fn location(&self) -> String {
+        match &self.name {
+            Some(name) => format!(" in {name}"),
+            None => String::new(),
+        }
+    }
+}
+
+/// The keys of a single file, deserialized on their own so type errors point
+/// at the file they're in.
+#[derive(Deserialize)]
+#[allow(dead_code)]
+struct Fragment {
+    settings: Option<Settings>,
+    #[serde(rename = "radial-menu")]
+    radial_menu: Option<Vec<RadialMenu>>,
+    commands: Option<Vec<CfgCommandEntry>>,
+    profiles: Option<Vec<Profile>>,
+    #[serde(default)]
+    groups: BTreeMap<String, Vec<CfgCommandEntry>>,
+    #[serde(default)]
+    include: Vec<String>,
+}
+
+/// Paths listed in the `include` of a file, if it parses.
+fn includes(content: &str) -> Vec<String> {
+    let value = content.parse::<Value>().ok();
+    let include = value.as_ref().and_then(|value| value.get("include")?.as_array().cloned());
+    include.unwrap_or_default().iter().filter_map(|path| Some(path.as_str()?.to_string())).collect()
+}
+
+/// Read the file at `path` and every file it includes, main file first.
+pub(crate) fn read(path: &Path) -> Result<Vec<Source>, String> {
+    let name = path.file_name().unwrap_or(path.as_os_str()).to_string_lossy().to_string();
+    let mut sources = Vec::new();
+    read_source(path, name, None, &mut Vec::new(), &mut sources)?;
+    Ok(sources)
+}
+
+fn read_source(
+    path: &Path,
+    name: String,
+    included_by: Option<&str>,
+    stack: &mut Vec<(PathBuf, String)>,
+    sources: &mut Vec<Source>,
+) -> Result<(), String> {
+    let read_error = |e: std::io::Error| match included_by {
+        Some(parent) => format!("Couldn't read {name}, included by {parent}: {e}"),
+        None => format!("Couldn't read config file: {e}"),
+    };
+
+    let canonical = path.canonicalize().map_err(read_error)?;
+    if let Some(start) = stack.iter().position(|(path, _)| *path == canonical) {
+        let cycle = stack[start..].iter().map(|(_, name)| name.as_str()).collect::<Vec<_>>();
+        return Err(format!("Include cycle: {} -> {name}", cycle.join(" -> ")));
+    }
+    if sources.iter().any(|source| source.path.as_ref() == Some(&canonical)) {
+        return Ok(());
+    }
+
+    let content = std::fs::read_to_string(path).map_err(read_error)?;
+    let includes = includes(&content);
+    sources.push(Source {
+        name: Some(name.clone()),
+        path: Some(canonical.clone()),
+        content,
+        included: included_by.is_some(),
+    });
+
+    stack.push((canonical, name.clone()));
+    let dir = path.parent().unwrap_or(Path::new(""));
+    let name_dir = Path::new(&name).parent().unwrap_or(Path::new(""));
+    for include in includes {
+        let include_name = name_dir.join(&include).to_string_lossy().to_string();
+        read_source(&dir.join(&include), include_name, Some(&name), stack, sources)?;
+    }
+    stack.pop();
+
+    Ok(())
+}
+
+/// Check, merge and deserialize the files making up a config.
+pub(crate) fn parse(sources: &[Source]) -> Result<Config, String> {
+    let values = sources.iter().map(|source| source.content.parse::<Value>().ok());
+    let Some(values) = values.collect::<Option<Vec<_>>>() else {
+        // Only report syntax errors, as the other files may refer to groups
+        // defined in the broken ones.
+        let errors = sources
+            .iter()
+            .filter(|source| source.content.parse::<Value>().is_err())
+            .filter_map(|source| check_source(source, &[]).err());
+        return Err(errors.collect::<Vec<_>>().join("\n"));
+    };
+
+    let mut groups = BTreeMap::<String, (&Source, Vec<Value>)>::new();
+    let mut errors = Vec::new();
+    for (source, value) in sources.iter().zip(&values) {
+        let Some(definitions) = value.get("groups").and_then(Value::as_table) else {
+            continue;
+        };
+        for (name, commands) in definitions {
+            let commands = commands.as_array().cloned().unwrap_or_default();
+            if let Some((other, _)) = groups.insert(name.clone(), (source, commands)) {
+                errors.push(format!(
+                    "TOML config error: group \"{name}\" is defined in both {} and {}",
+                    other.name(),
+                    source.name()
+                ));
+            }
+        }
+    }
+
+    let names = groups.keys().cloned().collect::<Vec<_>>();
+    errors.extend(sources.iter().filter_map(|source| check_source(source, &names).err()));
+    if !errors.is_empty() {
+        return Err(errors.join("\n"));
+    }
+
+    for source in sources {
+        deserialize::<Fragment>(source, &mut toml::de::Deserializer::new(&source.content))?;
+    }
+
+    let mut values = values.into_iter();
+    let mut config = values.next().unwrap_or_else(|| Value::Table(Default::default()));
+    if let Some(root) = config.as_table_mut() {
+        root.remove("include");
+        root.remove("groups");
+        for value in values {
+            for key in ["commands", "radial-menu"] {
+                if let Some(Value::Array(entries)) = value.get(key) {
+                    let merged = root.entry(key).or_insert_with(|| Value::Array(Vec::new()));
+                    if let Value::Array(merged) = merged {
+                        merged.extend(entries.iter().cloned());
+                    }
+                }
+            }
+        }
+
+        let mut expander = Expander { groups: &groups, stack: Vec::new() };
+        if let Some(Value::Array(commands)) = root.get_mut("commands") {
+            expander.expand(commands)?;
+        }
+        if let Some(Value::Array(profiles)) = root.get_mut("profiles") {
+            for profile in profiles {
+                if let Some(Value::Array(commands)) = profile.get_mut("commands") {
+                    expander.expand(commands)?;
+                }
+            }
+        }
+        for name in groups.keys() {
+            expander.resolve(name)?;
+        }
+    }
+
+    deserialize(&sources[0], config)
+}
+
+fn check_source(source: &Source, groups: &[String]) -> Result<(), String> {
+    check::check_file(&source.content, source.name.as_deref(), source.included, groups)
+}
+
+/// Deserialize, pointing errors at their key and line in `source`.
+fn deserialize<'de, T: Deserialize<'de>>(
+    source: &Source,
+    de: impl serde::Deserializer<'de>,
+) -> Result<T, String> {
+    serde_path_to_error::deserialize(de).map_err(|e| {
+        let location = check::locate(&source.content, e.path())
+            .map(|(line, column)| format!(" (line {line}, column {column})"))
+            .unwrap_or_default();
+        let file = source.location();
+        format!("TOML config error{file} at {}{location}: {}", e.path(), e.inner())
+    })
+}
+
+/// Replaces group references with the commands of the group.
+struct Expander<'a> {
+    groups: &'a BTreeMap<String, (&'a Source, Vec<Value>)>,
+    /// Groups being expanded, to detect groups that contain themselves.
+    stack: Vec<&'a str>,
+}
+
+impl<'a> Expander<'a> {
+    fn expand(&mut self, commands: &mut [Value]) -> Result<(), String> {
+        for command in commands {
+            let Some(table) = command.as_table_mut() else {
+                continue;
+            };
+            let Some(name) = table.get("group").and_then(Value::as_str).map(str::to_string) else {
+                continue;
+            };
+
+            match table.get_mut("commands") {
+                Some(Value::Array(commands)) => self.expand(commands)?,
+                Some(_) => {},
+                None => {
+                    let commands = self.resolve(&name)?;
+                    table.insert("commands".to_string(), Value::Array(commands));
+                },
+            }
+        }
+        Ok(())
+    }
+
+    /// The commands of the group called `name`, with references expanded.
+    fn resolve(&mut self, name: &str) -> Result<Vec<Value>, String> {
+        let Some((name, (source, commands))) = self.groups.get_key_value(name) else {
+            return Err(format!("TOML config error: group \"{name}\" is not defined"));
+        };
+
+        if self.stack.contains(&name.as_str()) {
+            let start = self.stack.iter().position(|group| group == name).unwrap_or_default();
+            let cycle = self.stack[start..].iter().map(|group| format!("\"{group}\" -> "));
+            return Err(format!(
+                "TOML config error{}: group \"{name}\" contains itself: {}\"{name}\"",
+                source.location(),
+                cycle.collect::<String>()
+            ));
+        }
+
+        self.stack.push(name);
+        let mut commands = commands.clone();
+        let expanded = self.expand(&mut commands);
+        self.stack.pop();
+        expanded.map(|()| commands)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixture(name: &str) -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/include").join(name)
+    }
+
+    fn group_commands<'a>(config: &'a Config, label: &str) -> &'a [CfgCommandEntry] {
+        config
+            .commands
+            .iter()
+            .find_map(|entry| match &entry.command {
+                crate::CfgCommand::Group { label: group, commands } if group == label => {
+                    Some(commands.as_slice())
+                },
+                _ => None,
+            })
+            .unwrap()
+    }
+
+    #[test]
+    fn test_include() {
+        let config = Config::load(&fixture("main.toml")).unwrap();
+
+        let includes = config.includes.iter().map(|path| path.file_name().unwrap());
+        assert_eq!(includes.collect::<Vec<_>>(), ["render_flags.toml", "hitboxes.toml"]);
+
+        // Own commands first, then the included ones.
+        assert_eq!(config.commands.len(), 4);
+        assert_eq!(config.radial_menu.len(), 2);
+        assert_eq!(group_commands(&config, "Render flags").len(), 3);
+        assert_eq!(group_commands(&config, "Hitboxes").len(), 2);
+    }
+
+    #[test]
+    fn test_include_cycle() {
+        let err = Config::load(&fixture("cycle_a.toml")).err().unwrap();
+        assert_eq!(err, "Include cycle: cycle_a.toml -> cycle_b.toml -> cycle_a.toml");
+    }
+
+    #[test]
+    fn test_include_missing() {
+        let err = Config::load(&fixture("missing.toml")).err().unwrap();
+        assert!(err.starts_with("Couldn't read nowhere.toml, included by missing.toml: "), "{err}");
+    }
+
+    #[test]
+    fn test_included_errors() {
+        let err = Config::load(&fixture("errors.toml")).err().unwrap();
+        assert_eq!(err.lines().collect::<Vec<_>>(), [
+            "TOML config error in errors_included.toml at line 1, column 2: `settings` can't be \
+             set in an included file, only `include`, `groups`, `commands` and `radial-menu`",
+            "TOML config error in errors_included.toml at line 5, column 20: \"Render flag\" is \
+             not a valid group, did you mean \"Render flags\"?",
+        ]);
+
+        let err = Config::load(&fixture("type_error.toml")).err().unwrap();
+        assert!(
+            err.starts_with(
+                "TOML config error in type_error_included.toml at radial-menu[0] (line 1, column \
+                 16): missing field `label`"
+            ),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn test_group_cycle() {
+        let err = Config::parse(
+            r#"radial-menu = []
+            commands = [{ group = "A" }]
+            [settings]
+            log_level = "INFO"
+            display = "0"
+            [groups]
+            A = [{ flag = "no_damage" }, { group = "B" }]
+            B = [{ group = "A" }]
+            "#,
+        )
+        .err()
+        .unwrap();
+        assert_eq!(err, "TOML config error: group \"A\" contains itself: \"A\" -> \"B\" -> \"A\"");
+    }
+}
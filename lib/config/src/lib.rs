@@ -0,0 +1,1066 @@
+//! Parsing and validation of `jdsd_er_practice_tool.toml`.
+//!
+//! Nothing in here depends on the game's memory, so the config can be parsed
+//! and checked on any platform. The practice tool turns the parsed commands
+//! into widgets.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use practice_tool_core::controller::ControllerCombination;
+use practice_tool_core::key::Key;
+use serde::Deserialize;
+use tracing::metadata::LevelFilter;
+
+mod check;
+mod custom_indicator;
+mod include;
+pub mod schema;
+
+pub use custom_indicator::{CustomIndicatorSpec, Term, ValueType, SYMBOL_NAMES};
+
+#[cfg_attr(test, derive(Debug))]
+#[derive(Deserialize, Clone)]
+pub struct Config {
+    pub settings: Settings,
+    #[serde(rename = "radial-menu")]
+    pub radial_menu: Vec<RadialMenu>,
+    pub commands: Vec<CfgCommandEntry>,
+    #[serde(default)]
+    pub profiles: Vec<Profile>,
+    /// Files included by the config file, directly or not.
+    #[serde(skip)]
+    pub includes: Vec<PathBuf>,
+}
+
+/// Name under which the top-level commands, radial menu and indicators are
+/// listed alongside the profiles.
+pub const DEFAULT_PROFILE: &str = "Default";
+
+/// An alternative set of commands, radial menu and indicators, selected in
+/// game. Whatever it leaves out is taken from the top level of the file.
+#[cfg_attr(test, derive(Debug))]
+#[derive(Deserialize, Clone)]
+pub struct Profile {
+    pub name: String,
+    #[serde(rename = "radial-menu")]
+    pub radial_menu: Option<Vec<RadialMenu>>,
+    pub indicators: Option<Vec<Indicator>>,
+    pub commands: Option<Vec<CfgCommandEntry>>,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct Settings {
+    pub log_level: LevelFilterSerde,
+    pub display: Key,
+    pub hide: Option<Key>,
+    /// Profile active when the tool starts.
+    pub profile: Option<String>,
+    /// Switches to the next profile. Unlike a profile picked in the tool,
+    /// it isn't saved as `profile`.
+    pub cycle_profile: Option<Key>,
+    #[serde(default)]
+    pub dxgi_debug: bool,
+    #[serde(default)]
+    pub show_console: bool,
+    #[serde(default)]
+    pub disable_update_prompt: bool,
+    #[serde(default = "Indicator::default_set")]
+    pub indicators: Vec<Indicator>,
+    pub radial_menu_open: Option<ControllerCombination>,
+    pub livesplit: Option<LiveSplitSettings>,
+    pub api: Option<ApiSettings>,
+    pub export: Option<ExportSettings>,
+    #[serde(default)]
+    pub hit_count: HitCountSettings,
+    #[serde(default)]
+    pub animation_history: AnimationHistorySettings,
+    #[serde(default)]
+    pub speedometer: SpeedometerSettings,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct LiveSplitSettings {
+    #[serde(default = "LiveSplitSettings::default_address")]
+    pub address: String,
+    pub split: Option<Key>,
+    pub reset: Option<Key>,
+    /// Reset the run whenever the IGT goes backwards, e.g. when loading a
+    /// savefile or switching characters.
+    #[serde(default)]
+    pub auto_reset: bool,
+}
+
+impl LiveSplitSettings {
+    fn default_address() -> String {
+        "localhost:16834".to_string()
+    }
+}
+
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+pub struct ApiSettings {
+    #[serde(default = "ApiSettings::default_port")]
+    pub port: u16,
+    /// Shared secret every request has to carry.
+    pub token: String,
+}
+
+impl ApiSettings {
+    fn default_port() -> u16 {
+        16835
+    }
+}
+
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+pub struct ExportSettings {
+    /// Milliseconds between exports.
+    #[serde(default = "ExportSettings::default_interval")]
+    pub interval: u64,
+    pub files: Vec<ExportFile>,
+}
+
+impl ExportSettings {
+    fn default_interval() -> u64 {
+        500
+    }
+}
+
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+pub struct ExportFile {
+    pub path: PathBuf,
+    pub template: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Clone, Default)]
+pub struct HitCountSettings {
+    /// Start a new segment on LiveSplit splits and clear all hits on resets.
+    /// Only the splits and resets made with the `livesplit` hotkeys are seen.
+    #[serde(default)]
+    pub split_linked: bool,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct AnimationHistorySettings {
+    /// Number of animations kept in the history.
+    #[serde(default = "AnimationHistorySettings::default_length")]
+    pub length: usize,
+}
+
+impl AnimationHistorySettings {
+    fn default_length() -> usize {
+        10
+    }
+}
+
+impl Default for AnimationHistorySettings {
+    fn default() -> Self {
+        AnimationHistorySettings { length: Self::default_length() }
+    }
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct SpeedometerSettings {
+    /// Averaging time constant in seconds. Zero shows raw speeds.
+    #[serde(default = "SpeedometerSettings::default_smoothing")]
+    pub smoothing: f32,
+    /// Seconds the peak speed is held for.
+    #[serde(default = "SpeedometerSettings::default_peak_hold")]
+    pub peak_hold: f32,
+}
+
+impl SpeedometerSettings {
+    fn default_smoothing() -> f32 {
+        0.1
+    }
+
+    fn default_peak_hold() -> f32 {
+        2.
+    }
+}
+
+impl Default for SpeedometerSettings {
+    fn default() -> Self {
+        SpeedometerSettings {
+            smoothing: Self::default_smoothing(),
+            peak_hold: Self::default_peak_hold(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct RadialMenu {
+    pub key: Key,
+    pub label: String,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub enum IndicatorType {
+    Igt,
+    Position,
+    PositionChange,
+    PositionDistance,
+    Speedometer,
+    GameVersion,
+    ImguiDebug,
+    Fps,
+    FrameCount,
+    Animation,
+    AnimationHistory,
+    Deaths,
+    HitCount,
+    #[serde(skip)]
+    Custom(Box<CustomIndicatorSpec>),
+}
+
+#[derive(Debug, Deserialize, Clone)]
+#[serde(try_from = "IndicatorConfig")]
+pub struct Indicator {
+    pub indicator: IndicatorType,
+    pub enabled: bool,
+    /// Position of the indicator's own window, in 1920x1080 screen units.
+    /// Indicators without one are stacked in the main window.
+    pub position: Option<[f32; 2]>,
+    pub anchor: Anchor,
+    pub font_size: Option<f32>,
+    pub color: Option<[f32; 4]>,
+    /// Template that replaces the indicator's default text.
+    pub format: Option<String>,
+}
+
+impl Indicator {
+    fn new(indicator: IndicatorType, enabled: bool) -> Self {
+        Indicator {
+            indicator,
+            enabled,
+            position: None,
+            anchor: Anchor::default(),
+            font_size: None,
+            color: None,
+            format: None,
+        }
+    }
+
+    fn default_set() -> Vec<Indicator> {
+        vec![
+            Indicator::new(IndicatorType::GameVersion, true),
+            Indicator::new(IndicatorType::Igt, true),
+            Indicator::new(IndicatorType::Position, false),
+            Indicator::new(IndicatorType::PositionChange, false),
+            Indicator::new(IndicatorType::PositionDistance, false),
+            Indicator::new(IndicatorType::Speedometer, false),
+            Indicator::new(IndicatorType::Animation, false),
+            Indicator::new(IndicatorType::AnimationHistory, false),
+            Indicator::new(IndicatorType::Fps, false),
+            Indicator::new(IndicatorType::FrameCount, false),
+            Indicator::new(IndicatorType::ImguiDebug, false),
+            Indicator::new(IndicatorType::Deaths, false),
+            Indicator::new(IndicatorType::HitCount, false),
+        ]
+    }
+}
+
+/// Which point of an indicator's window sits at its configured position.
+#[derive(Debug, Deserialize, Clone, Copy, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum Anchor {
+    #[default]
+    TopLeft,
+    Top,
+    TopRight,
+    Left,
+    Center,
+    Right,
+    BottomLeft,
+    Bottom,
+    BottomRight,
+}
+
+impl Anchor {
+    pub fn pivot(self) -> [f32; 2] {
+        match self {
+            Anchor::TopLeft => [0., 0.],
+            Anchor::Top => [0.5, 0.],
+            Anchor::TopRight => [1., 0.],
+            Anchor::Left => [0., 0.5],
+            Anchor::Center => [0.5, 0.5],
+            Anchor::Right => [1., 0.5],
+            Anchor::BottomLeft => [0., 1.],
+            Anchor::Bottom => [0.5, 1.],
+            Anchor::BottomRight => [1., 1.],
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Clone)]
+struct IndicatorConfig {
+    indicator: String,
+    #[serde(default = "IndicatorConfig::default_enabled")]
+    enabled: bool,
+    position: Option<[f32; 2]>,
+    #[serde(default)]
+    anchor: Anchor,
+    font_size: Option<f32>,
+    color: Option<String>,
+    format: Option<String>,
+    // Custom indicators only
+    label: Option<String>,
+    expr: Option<String>,
+    #[serde(rename = "type")]
+    value_type: Option<ValueType>,
+    min: Option<f64>,
+    max: Option<f64>,
+}
+
+impl IndicatorConfig {
+    fn default_enabled() -> bool {
+        true
+    }
+}
+
+/// Names accepted by the `indicator` key.
+pub const INDICATOR_NAMES: &[&str] = &[
+    "igt",
+    "position",
+    "position_change",
+    "position_distance",
+    "speedometer",
+    "animation",
+    "animation_history",
+    "game_version",
+    "fps",
+    "framecount",
+    "imgui_debug",
+    "deaths",
+    "hit_count",
+    "custom",
+];
+
+impl IndicatorType {
+    /// Value of the `indicator` key for this type.
+    pub fn name(&self) -> &'static str {
+        match self {
+            IndicatorType::Igt => "igt",
+            IndicatorType::Position => "position",
+            IndicatorType::PositionChange => "position_change",
+            IndicatorType::PositionDistance => "position_distance",
+            IndicatorType::Speedometer => "speedometer",
+            IndicatorType::Animation => "animation",
+            IndicatorType::AnimationHistory => "animation_history",
+            IndicatorType::GameVersion => "game_version",
+            IndicatorType::Fps => "fps",
+            IndicatorType::FrameCount => "framecount",
+            IndicatorType::ImguiDebug => "imgui_debug",
+            IndicatorType::Deaths => "deaths",
+            IndicatorType::HitCount => "hit_count",
+            IndicatorType::Custom(_) => "custom",
+        }
+    }
+}
+
+impl TryFrom<IndicatorConfig> for Indicator {
+    type Error = String;
+
+    fn try_from(indicator: IndicatorConfig) -> Result<Self, Self::Error> {
+        let indicator_type = match indicator.indicator.as_str() {
+            "igt" => IndicatorType::Igt,
+            "position" => IndicatorType::Position,
+            "position_change" => IndicatorType::PositionChange,
+            "position_distance" => IndicatorType::PositionDistance,
+            "speedometer" => IndicatorType::Speedometer,
+            "animation" => IndicatorType::Animation,
+            "animation_history" => IndicatorType::AnimationHistory,
+            "game_version" => IndicatorType::GameVersion,
+            "fps" => IndicatorType::Fps,
+            "framecount" => IndicatorType::FrameCount,
+            "imgui_debug" => IndicatorType::ImguiDebug,
+            "deaths" => IndicatorType::Deaths,
+            "hit_count" => IndicatorType::HitCount,
+            "custom" => {
+                let (Some(label), Some(expr), Some(value_type)) =
+                    (indicator.label.clone(), indicator.expr.as_deref(), indicator.value_type)
+                else {
+                    return Err("Custom indicators need a label, an expr and a type".to_string());
+                };
+                IndicatorType::Custom(Box::new(CustomIndicatorSpec::new(
+                    label,
+                    expr,
+                    value_type,
+                    indicator.min,
+                    indicator.max,
+                )?))
+            },
+            value => return Err(format!("Unrecognized indicator: {value}")),
+        };
+
+        let color = indicator.color.as_deref().map(parse_color).transpose()?;
+
+        Ok(Indicator {
+            indicator: indicator_type,
+            enabled: indicator.enabled,
+            position: indicator.position,
+            anchor: indicator.anchor,
+            font_size: indicator.font_size.filter(|&size| size > 0.),
+            color,
+            format: indicator.format,
+        })
+    }
+}
+
+/// Parse a `#rrggbb` or `#rrggbbaa` color.
+fn parse_color(color: &str) -> Result<[f32; 4], String> {
+    let hex = color.strip_prefix('#').unwrap_or(color);
+    if !(hex.len() == 6 || hex.len() == 8) || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(format!("Invalid color {color:?}, expected \"#rrggbb\" or \"#rrggbbaa\""));
+    }
+
+    let channel = |i: usize| u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16).unwrap() as f32 / 255.;
+    let alpha = if hex.len() == 8 { channel(3) } else { 1. };
+    Ok([channel(0), channel(1), channel(2), alpha])
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(untagged)]
+pub enum PlaceholderOption<T> {
+    Data(T),
+    #[allow(dead_code)]
+    Placeholder(bool),
+}
+
+impl<T> PlaceholderOption<T> {
+    pub fn into_option(self) -> Option<T> {
+        match self {
+            PlaceholderOption::Data(d) => Some(d),
+            PlaceholderOption::Placeholder(_) => None,
+        }
+    }
+
+    pub fn as_option(&self) -> Option<&T> {
+        match self {
+            PlaceholderOption::Data(d) => Some(d),
+            PlaceholderOption::Placeholder(_) => None,
+        }
+    }
+}
+
+#[cfg_attr(test, derive(Debug))]
+#[derive(Deserialize, Clone)]
+pub struct CfgCommandEntry {
+    #[serde(flatten)]
+    pub command: CfgCommand,
+    /// Whether the command's hotkeys are meant to be shared with others.
+    #[serde(default)]
+    pub allow_shared: bool,
+}
+
+#[cfg_attr(test, derive(Debug))]
+#[derive(Deserialize, Clone)]
+#[serde(untagged)]
+pub enum CfgCommand {
+    SavefileManager {
+        #[serde(rename = "savefile_manager")]
+        hotkey_load: PlaceholderOption<Key>,
+    },
+    ItemSpawner {
+        #[serde(rename = "item_spawner")]
+        hotkey_load: PlaceholderOption<Key>,
+    },
+    Flag {
+        flag: FlagSpec,
+        hotkey: Option<Key>,
+    },
+    MultiFlag {
+        flag: MultiFlagSpec,
+        hotkey: Option<Key>,
+    },
+    SpecialFlag {
+        flag: String,
+        hotkey: Option<Key>,
+    },
+    MultiFlagUser {
+        flags: Vec<FlagSpec>,
+        hotkey: Option<Key>,
+        label: String,
+    },
+    Label {
+        #[serde(rename = "label")]
+        label: String,
+    },
+    Position {
+        position: PlaceholderOption<Key>,
+        save: Option<Key>,
+    },
+    NudgePosition {
+        nudge: f32,
+        nudge_up: Option<Key>,
+        nudge_down: Option<Key>,
+    },
+    CycleSpeed {
+        #[serde(rename = "cycle_speed")]
+        cycle_speed: Vec<f32>,
+        hotkey: Option<Key>,
+    },
+    CycleColor {
+        #[serde(rename = "cycle_color")]
+        cycle_color: Vec<i32>,
+        hotkey: Option<Key>,
+    },
+    CharacterStats {
+        #[serde(rename = "character_stats")]
+        hotkey_open: PlaceholderOption<Key>,
+    },
+    Runes {
+        #[serde(rename = "runes")]
+        amount: u32,
+        hotkey: Option<Key>,
+    },
+    Target {
+        #[serde(rename = "target")]
+        hotkey: PlaceholderOption<Key>,
+        pin: Option<Key>,
+        #[serde(default)]
+        track: usize,
+    },
+    DamageLog {
+        #[serde(rename = "damage_log")]
+        hotkey: PlaceholderOption<Key>,
+        #[serde(default = "CfgCommand::default_dps_window")]
+        dps_window: f32,
+    },
+    FrameAdvance {
+        #[serde(rename = "frame_advance")]
+        hotkey: PlaceholderOption<Key>,
+        step: Option<Key>,
+        #[serde(default = "CfgCommand::default_frames")]
+        frames: u32,
+    },
+    Warp {
+        #[serde(rename = "warp")]
+        _warp: bool,
+    },
+    Group {
+        #[serde(rename = "group")]
+        label: String,
+        /// Left out in references to groups defined under `[groups]`, which
+        /// are expanded before the config is deserialized.
+        #[serde(default)]
+        commands: Vec<CfgCommandEntry>,
+    },
+    Quitout {
+        #[serde(rename = "quitout")]
+        hotkey: PlaceholderOption<Key>,
+    },
+}
+
+impl CfgCommand {
+    fn default_dps_window() -> f32 {
+        5.
+    }
+
+    fn default_frames() -> u32 {
+        1
+    }
+
+    /// Hotkeys bound by the command, with a label for each. Commands in a
+    /// group are not included.
+    pub fn hotkeys(&self) -> Vec<(String, Key)> {
+        let hotkeys = match self {
+            CfgCommand::Flag { flag, hotkey } => vec![(flag.label.clone(), *hotkey)],
+            CfgCommand::MultiFlag { flag, hotkey } => vec![(flag.label.clone(), *hotkey)],
+            CfgCommand::SpecialFlag { flag, hotkey } => vec![(flag.clone(), *hotkey)],
+            CfgCommand::MultiFlagUser { label, hotkey, .. } => vec![(label.clone(), *hotkey)],
+            CfgCommand::SavefileManager { hotkey_load } => {
+                vec![("Savefile manager".to_string(), hotkey_load.as_option().copied())]
+            },
+            CfgCommand::ItemSpawner { hotkey_load } => {
+                vec![("Item spawner".to_string(), hotkey_load.as_option().copied())]
+            },
+            CfgCommand::Position { position, save } => vec![
+                ("Load position".to_string(), position.as_option().copied()),
+                ("Save position".to_string(), *save),
+            ],
+            CfgCommand::NudgePosition { nudge_up, nudge_down, .. } => {
+                vec![("Nudge up".to_string(), *nudge_up), ("Nudge down".to_string(), *nudge_down)]
+            },
+            CfgCommand::CycleSpeed { hotkey, .. } => vec![("Cycle speed".to_string(), *hotkey)],
+            CfgCommand::CycleColor { hotkey, .. } => {
+                vec![("Cycle mesh color".to_string(), *hotkey)]
+            },
+            CfgCommand::CharacterStats { hotkey_open } => {
+                vec![("Character stats".to_string(), hotkey_open.as_option().copied())]
+            },
+            CfgCommand::Runes { amount, hotkey } => vec![(format!("Add {amount} runes"), *hotkey)],
+            CfgCommand::Target { hotkey, pin, .. } => vec![
+                ("Target entity info".to_string(), hotkey.as_option().copied()),
+                ("Pin target".to_string(), *pin),
+            ],
+            CfgCommand::DamageLog { hotkey, .. } => {
+                vec![("Damage log".to_string(), hotkey.as_option().copied())]
+            },
+            CfgCommand::FrameAdvance { hotkey, step, .. } => vec![
+                ("Pause game".to_string(), hotkey.as_option().copied()),
+                ("Advance frames".to_string(), *step),
+            ],
+            CfgCommand::Quitout { hotkey } => {
+                vec![("Quitout".to_string(), hotkey.as_option().copied())]
+            },
+            CfgCommand::Label { .. } | CfgCommand::Warp { .. } | CfgCommand::Group { .. } => {
+                Vec::new()
+            },
+        };
+
+        hotkeys.into_iter().filter_map(|(label, key)| Some((label, key?))).collect()
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(try_from = "String")]
+pub struct LevelFilterSerde(LevelFilter);
+
+impl LevelFilterSerde {
+    pub fn inner(&self) -> LevelFilter {
+        self.0
+    }
+}
+
+impl TryFrom<String> for LevelFilterSerde {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        Ok(LevelFilterSerde(
+            LevelFilter::from_str(&value)
+                .map_err(|e| format!("Couldn't parse log level filter: {e}"))?,
+        ))
+    }
+}
+
+impl Config {
+    /// Parse a config file on its own. Includes are ignored, so groups must
+    /// be defined in the same file.
+    pub fn parse(cfg: &str) -> Result<Self, String> {
+        include::parse(&[include::Source {
+            name: None,
+            path: None,
+            content: cfg.to_string(),
+            included: false,
+        }])
+    }
+
+    /// Read and parse the config file at `path`, with the files it includes.
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let sources = include::read(path)?;
+        let mut config = include::parse(&sources)?;
+        config.includes = sources.into_iter().skip(1).filter_map(|source| source.path).collect();
+        Ok(config)
+    }
+}
+
+impl Config {
+    /// Names of the profiles that can be selected, starting with the
+    /// top-level one.
+    pub fn profile_names(&self) -> Vec<&str> {
+        std::iter::once(DEFAULT_PROFILE)
+            .chain(self.profiles.iter().map(|profile| profile.name.as_str()))
+            .collect()
+    }
+
+    /// Index in [`Config::profile_names`] of the profile named by
+    /// `settings.profile`, or of the top-level one.
+    pub fn active_profile(&self) -> usize {
+        self.settings
+            .profile
+            .as_deref()
+            .and_then(|name| self.profiles.iter().position(|profile| profile.name == name))
+            .map_or(0, |index| index + 1)
+    }
+
+    /// The config as seen with the profile at `index` in
+    /// [`Config::profile_names`] active, without the other profiles.
+    pub fn with_profile(&self, index: usize) -> Config {
+        let profile = index.checked_sub(1).and_then(|index| self.profiles.get(index));
+
+        let mut settings = self.settings.clone();
+        if let Some(indicators) = profile.and_then(|profile| profile.indicators.as_ref()) {
+            settings.indicators = indicators.clone();
+        }
+
+        Config {
+            settings,
+            radial_menu: profile
+                .and_then(|profile| profile.radial_menu.clone())
+                .unwrap_or_else(|| self.radial_menu.clone()),
+            commands: profile
+                .and_then(|profile| profile.commands.clone())
+                .unwrap_or_else(|| self.commands.clone()),
+            profiles: Vec::new(),
+            includes: self.includes.clone(),
+        }
+    }
+
+    /// The indicators shown with the profile at `index` active: its own, or
+    /// the top-level ones if it doesn't define any.
+    pub fn indicators_mut(&mut self, index: usize) -> &mut Vec<Indicator> {
+        let profile = index.checked_sub(1).and_then(|index| self.profiles.get_mut(index));
+        match profile.and_then(|profile| profile.indicators.as_mut()) {
+            Some(indicators) => indicators,
+            None => &mut self.settings.indicators,
+        }
+    }
+}
+
+/// Where a hotkey is bound.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BindingSource {
+    Settings,
+    Command { allow_shared: bool },
+    RadialMenu,
+}
+
+#[derive(Debug, Clone)]
+pub struct Binding {
+    pub key: Key,
+    pub label: String,
+    pub source: BindingSource,
+}
+
+impl Config {
+    /// Every hotkey in the config: the settings, all commands including the
+    /// ones in groups, and the radial menu entries.
+    pub fn bindings(&self) -> Vec<Binding> {
+        let livesplit = self.settings.livesplit.as_ref();
+        let settings = [
+            ("Open/close tool", Some(self.settings.display)),
+            ("Hide tool", self.settings.hide),
+            ("Next profile", self.settings.cycle_profile),
+            ("LiveSplit split", livesplit.and_then(|livesplit| livesplit.split)),
+            ("LiveSplit reset", livesplit.and_then(|livesplit| livesplit.reset)),
+        ];
+
+        let mut bindings = settings
+            .into_iter()
+            .filter_map(|(label, key)| {
+                Some(Binding {
+                    key: key?,
+                    label: label.to_string(),
+                    source: BindingSource::Settings,
+                })
+            })
+            .collect::<Vec<_>>();
+
+        command_bindings(&self.commands, "", &mut bindings);
+
+        bindings.extend(self.radial_menu.iter().map(|entry| Binding {
+            key: entry.key,
+            label: entry.label.clone(),
+            source: BindingSource::RadialMenu,
+        }));
+
+        bindings
+    }
+
+    /// Keys bound more than once by mistake, one message each. A key can be
+    /// shared when at most one of its bindings lacks `allow_shared`. Radial
+    /// menu entries send the keys of other bindings, so they are only
+    /// compared with each other.
+    pub fn hotkey_conflicts(&self) -> Vec<String> {
+        let bindings = self.bindings();
+
+        let mut by_key = BTreeMap::<(bool, String), Vec<&Binding>>::new();
+        for binding in &bindings {
+            let is_radial = binding.source == BindingSource::RadialMenu;
+            by_key.entry((is_radial, binding.key.to_string())).or_default().push(binding);
+        }
+
+        by_key
+            .into_iter()
+            .filter(|(_, bindings)| {
+                let exclusive = bindings
+                    .iter()
+                    .filter(|b| b.source != BindingSource::Command { allow_shared: true })
+                    .count();
+                exclusive > 1
+            })
+            .map(|((is_radial, key), bindings)| {
+                let labels =
+                    bindings.iter().map(|b| format!("\"{}\"", b.label)).collect::<Vec<_>>();
+                if is_radial {
+                    format!("Radial menu entries {} send the same hotkey {key}", labels.join(", "))
+                } else {
+                    format!(
+                        "Hotkey {key} is bound to {}. Set allow_shared = true on the commands \
+                         that are meant to share it.",
+                        labels.join(", ")
+                    )
+                }
+            })
+            .collect()
+    }
+}
+
+fn command_bindings(commands: &[CfgCommandEntry], prefix: &str, bindings: &mut Vec<Binding>) {
+    for entry in commands {
+        let source = BindingSource::Command { allow_shared: entry.allow_shared };
+        for (label, key) in entry.command.hotkeys() {
+            bindings.push(Binding { key, label: format!("{prefix}{label}"), source });
+        }
+
+        if let CfgCommand::Group { label, commands } = &entry.command {
+            command_bindings(commands, &format!("{prefix}{label} / "), bindings);
+        }
+    }
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            settings: Settings {
+                log_level: LevelFilterSerde(LevelFilter::DEBUG),
+                display: "0".parse().unwrap(),
+                hide: "rshift+0".parse().ok(),
+                profile: None,
+                cycle_profile: None,
+                dxgi_debug: false,
+                show_console: false,
+                indicators: Indicator::default_set(),
+                disable_update_prompt: false,
+                radial_menu_open: ControllerCombination::try_from("l3+r3").ok(),
+                livesplit: None,
+                api: None,
+                export: None,
+                hit_count: Default::default(),
+                animation_history: Default::default(),
+                speedometer: Default::default(),
+            },
+            radial_menu: Vec::new(),
+            commands: Vec::new(),
+            profiles: Vec::new(),
+            includes: Vec::new(),
+        }
+    }
+}
+
+/// A flag toggled by `flag` and `flags` commands.
+#[derive(Debug, Deserialize, Clone)]
+#[serde(try_from = "String")]
+pub struct FlagSpec {
+    pub name: String,
+    pub label: String,
+}
+
+impl FlagSpec {
+    fn new(name: &str, label: &str) -> FlagSpec {
+        FlagSpec { name: name.to_string(), label: label.to_string() }
+    }
+}
+
+/// Invokes `$callback! { (name, "Label"), ... }` with every flag accepted by
+/// `flag` and `flags` commands, so the practice tool can map the same names
+/// to its pointers.
+#[macro_export]
+macro_rules! for_each_flag {
+    ($callback:ident) => {
+        $callback! {
+            (one_shot, "One shot"),
+            (no_damage, "All no damage"),
+            (no_dead, "No death"),
+            (no_hit, "No hit"),
+            (no_goods_consume, "Inf Consumables"),
+            (no_stamina_consume, "Inf Stamina"),
+            (no_fp_consume, "Inf Focus"),
+            (no_ashes_of_war_fp_consume, "Inf Focus (AoW)"),
+            (no_arrows_consume, "Inf arrows"),
+            (no_attack, "No attack"),
+            (no_move, "No move"),
+            (no_update_ai, "No update AI"),
+            (no_trigger_event, "No trigger events"),
+            (runearc, "Rune Arc"),
+            (gravity, "No Gravity"),
+            (torrent_gravity, "No Gravity (Torrent)"),
+            (collision, "No Collision"),
+            (torrent_collision, "No Collision (Torrent)"),
+            (action_freeze, "Action freeze"),
+            (display_stable_pos, "Show stable pos"),
+            (weapon_hitbox1, "Weapon hitbox #1"),
+            (weapon_hitbox2, "Weapon hitbox #2"),
+            (weapon_hitbox3, "Weapon hitbox #3"),
+            (hitbox_high, "High world hitbox"),
+            (hitbox_low, "Low world hitbox"),
+            (hitbox_f, "Walls hitbox"),
+            (hitbox_character, "Character hitbox"),
+            (hitbox_event, "Event hitbox"),
+            (poise_view, "Poise View"),
+            (sound_view, "Sound View"),
+            (all_targeting_view, "Targeting View"),
+            (field_area_direction, "Direction HUD"),
+            (field_area_altimeter, "Altimeter HUD"),
+            (field_area_compass, "Compass HUD"),
+            // (show_map, "Show/hide map"),
+            (show_chr, "Show/hide character"),
+            (show_all_map_layers, "Show all map layers"),
+            (show_all_graces, "Show all graces"),
+        }
+    };
+}
+
+macro_rules! flag_specs {
+    ($( ($flag_name:ident, $flag_label:expr), )*) => {
+        /// Names accepted by `flag` and `flags` commands.
+        pub const FLAG_NAMES: &[&str] = &[$(stringify!($flag_name),)*];
+
+        fn flag_spec(name: &str) -> Option<FlagSpec> {
+            match name {
+                $(stringify!($flag_name) => Some(FlagSpec::new(stringify!($flag_name), $flag_label)),)*
+                _ => None,
+            }
+        }
+    }
+}
+
+for_each_flag!(flag_specs);
+
+impl TryFrom<String> for FlagSpec {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        flag_spec(&value).ok_or_else(|| format!("\"{value}\" is not a valid flag specifier"))
+    }
+}
+
+/// Names accepted by `flag` commands that toggle several flags at once.
+pub const MULTIFLAG_NAMES: &[&str] = &["show_map"];
+
+/// Names accepted by `flag` commands backed by a dedicated widget.
+pub const SPECIAL_FLAG_NAMES: &[&str] = &["deathcam"];
+
+/// A group of flags toggled together by a single `flag` command.
+#[derive(Debug, Deserialize, Clone)]
+#[serde(try_from = "String")]
+pub struct MultiFlagSpec {
+    pub name: String,
+    pub label: String,
+}
+
+impl MultiFlagSpec {
+    fn new(name: &str, label: &str) -> MultiFlagSpec {
+        MultiFlagSpec { name: name.to_string(), label: label.to_string() }
+    }
+}
+
+impl TryFrom<String> for MultiFlagSpec {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        match value.as_str() {
+            "show_map" => Ok(MultiFlagSpec::new("show_map", "Show/hide map")),
+            e => Err(format!("\"{e}\" is not a valid multiflag specifier")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Config, Indicator, IndicatorType, INDICATOR_NAMES};
+
+    #[test]
+    fn test_parse_ok() {
+        println!(
+            "{:?}",
+            toml::from_str::<toml::Value>(include_str!("../../../jdsd_er_practice_tool.toml"))
+        );
+        println!("{:?}", Config::parse(include_str!("../../../jdsd_er_practice_tool.toml")));
+    }
+
+    #[test]
+    fn test_indicator_names() {
+        for name in INDICATOR_NAMES.iter().filter(|&&name| name != "custom") {
+            let toml = format!("indicator = {name:?}");
+            let indicator: Indicator = toml::from_str(&toml).unwrap();
+            assert_eq!(indicator.indicator.name(), *name);
+        }
+    }
+
+    #[test]
+    fn test_parse_error_location() {
+        let err = Config::parse(include_str!("../tests/fixtures/flag_typo.toml")).unwrap_err();
+        assert!(err.starts_with("TOML config error at line 3, column 12: "), "{err}");
+
+        let err = Config::parse(include_str!("../tests/fixtures/invalid_value.toml")).unwrap_err();
+        assert!(err.starts_with("TOML config error at settings.log_level (line 2, column 13): "));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        println!(
+            "{:#?}",
+            Config::parse(
+                r#"commands = [ { boh = 3 } ]
+                [settings]
+                log_level = "DEBUG"
+                "#
+            )
+        );
+    }
+
+    #[test]
+    fn test_profiles() {
+        let mut config = Config::parse(
+            r#"radial-menu = [{ key = "p", label = "Quitout" }]
+            commands = [{ flag = "no_damage", hotkey = "1" }, { quitout = "p" }]
+            [settings]
+            log_level = "INFO"
+            display = "0"
+            profile = "Bosses"
+            indicators = [{ indicator = "igt", enabled = true }]
+
+            [[profiles]]
+            name = "Bosses"
+            commands = [{ flag = "one_shot", hotkey = "1" }]
+            indicators = [{ indicator = "fps", enabled = true }]
+
+            [[profiles]]
+            name = "Radial"
+            radial-menu = []
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(config.profile_names(), ["Default", "Bosses", "Radial"]);
+        assert_eq!(config.active_profile(), 1);
+
+        let bosses = config.with_profile(1);
+        assert!(bosses.profiles.is_empty());
+        assert_eq!(bosses.commands.len(), 1);
+        assert_eq!(bosses.radial_menu.len(), 1);
+        assert!(matches!(bosses.settings.indicators[0].indicator, IndicatorType::Fps));
+
+        let radial = config.with_profile(2);
+        assert_eq!(radial.commands.len(), 2);
+        assert!(radial.radial_menu.is_empty());
+        assert!(matches!(radial.settings.indicators[0].indicator, IndicatorType::Igt));
+
+        config.indicators_mut(2)[0].enabled = false;
+        assert!(!config.settings.indicators[0].enabled);
+        assert!(config.with_profile(1).settings.indicators[0].enabled);
+    }
+
+    #[test]
+    fn test_hotkey_conflicts() {
+        let config = Config::parse(
+            r#"radial-menu = []
+            commands = [
+                { flag = "no_damage", hotkey = "f1" },
+                { flag = "one_shot", hotkey = "f1" },
+                { flag = "gravity", hotkey = "f2", allow_shared = true },
+                { flag = "torrent_gravity", hotkey = "f2", allow_shared = true },
+            ]
+            [settings]
+            log_level = "INFO"
+            display = "0"
+            "#,
+        )
+        .unwrap();
+        let conflicts = config.hotkey_conflicts();
+        assert_eq!(conflicts.len(), 1, "{conflicts:?}");
+        assert!(conflicts[0].contains("\"One shot\""), "{conflicts:?}");
+    }
+}
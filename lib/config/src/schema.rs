@@ -0,0 +1,394 @@
+//! JSON Schema of the config file, for editors that validate and complete
+//! TOML against one.
+//!
+//! Commands are described from the same shapes used by [`check`], and names
+//! from the same lists, so the schema can't drift from what the parser
+//! accepts. Run `cargo xtask schema` after changing them.
+//!
+//! [`check`]: crate::check
+
+use serde_json::{json, Map, Value};
+
+use crate::check::{Shape, SHAPES};
+use crate::{DEFAULT_PROFILE, FLAG_NAMES, INDICATOR_NAMES, MULTIFLAG_NAMES, SPECIAL_FLAG_NAMES};
+
+/// File name of the generated schema, next to the bundled config file.
+pub const SCHEMA_FILE: &str = "jdsd_er_practice_tool.schema.json";
+
+/// Modifiers, each followed by `+`, then a key name.
+const HOTKEY_PATTERN: &str = r"^(([lr]?(ctrl|shift|alt|super))\+){0,3}[^+]+$";
+
+const HOTKEY_DESCRIPTION: &str = "A key, optionally preceded by up to 3 modifiers separated by \
+                                  `+`, e.g. \"ctrl+rshift+q\". Modifiers are ctrl, shift, alt and \
+                                  super, or their l/r prefixed variants.";
+
+const LOG_LEVELS: &[&str] = &[
+    "OFF", "ERROR", "WARN", "INFO", "DEBUG", "TRACE", "off", "error", "warn", "info", "debug",
+    "trace",
+];
+
+const ANCHORS: &[&str] = &[
+    "top_left",
+    "top",
+    "top_right",
+    "left",
+    "center",
+    "right",
+    "bottom_left",
+    "bottom",
+    "bottom_right",
+];
+
+const VALUE_TYPES: &[&str] = &["u8", "u16", "u32", "u64", "i8", "i16", "i32", "i64", "f32", "f64"];
+
+fn reference(name: &str) -> Value {
+    json!({ "$ref": format!("#/definitions/{name}") })
+}
+
+fn names(lists: &[&[&str]]) -> Value {
+    json!({ "enum": lists.concat() })
+}
+
+/// Schema of a key of a command.
+fn command_key(key: &str) -> Value {
+    match key {
+        "hotkey" | "save" | "nudge_up" | "nudge_down" | "pin" | "step" => reference("hotkey"),
+        "savefile_manager" | "item_spawner" | "position" | "character_stats" | "target"
+        | "damage_log" | "frame_advance" | "quitout" => reference("hotkey_or_true"),
+        "flag" => names(&[FLAG_NAMES, MULTIFLAG_NAMES, SPECIAL_FLAG_NAMES]),
+        "flags" => json!({ "type": "array", "items": names(&[FLAG_NAMES]) }),
+        "label" | "group" => json!({ "type": "string" }),
+        "nudge" | "dps_window" => json!({ "type": "number" }),
+        "cycle_speed" => json!({ "type": "array", "items": { "type": "number" } }),
+        "cycle_color" => json!({ "type": "array", "items": { "type": "integer" } }),
+        "runes" | "frames" | "track" => json!({ "type": "integer", "minimum": 0 }),
+        "warp" => json!({ "type": "boolean" }),
+        "commands" => json!({ "type": "array", "items": reference("command") }),
+        "allow_shared" => json!({
+            "type": "boolean",
+            "description": "Allow other commands to share this command's hotkeys.",
+        }),
+        key => panic!("No schema for command key `{key}`"),
+    }
+}
+
+fn command(shape: &Shape) -> Value {
+    let keys = [shape.key, "allow_shared"].into_iter().chain(shape.required.iter().copied());
+    let keys = keys.chain(shape.optional.iter().copied());
+    let properties = keys.map(|key| (key.to_string(), command_key(key))).collect::<Map<_, _>>();
+    let required =
+        [shape.key].into_iter().chain(shape.required.iter().copied()).collect::<Vec<_>>();
+
+    json!({
+        "type": "object",
+        "description": shape.example,
+        "properties": properties,
+        "required": required,
+        "additionalProperties": false,
+    })
+}
+
+fn indicator() -> Value {
+    json!({
+        "type": "object",
+        "properties": {
+            "indicator": names(&[INDICATOR_NAMES]),
+            "enabled": { "type": "boolean" },
+            "position": {
+                "type": "array",
+                "items": { "type": "number" },
+                "minItems": 2,
+                "maxItems": 2,
+                "description": "Position of the indicator's own window, in 1920x1080 screen units.",
+            },
+            "anchor": { "enum": ANCHORS },
+            "font_size": { "type": "number" },
+            "color": { "type": "string", "pattern": "^#?([0-9a-fA-F]{6}|[0-9a-fA-F]{8})$" },
+            "format": { "type": "string" },
+            "label": { "type": "string" },
+            "expr": {
+                "type": "string",
+                "description": "Pointer chain of a custom indicator, e.g. \
+                                \"world_chr_man+player_ins+0x190+0+0x154\".",
+            },
+            "type": { "enum": VALUE_TYPES },
+            "min": { "type": "number" },
+            "max": { "type": "number" },
+        },
+        "required": ["indicator"],
+        "additionalProperties": false,
+    })
+}
+
+fn settings() -> Value {
+    json!({
+        "type": "object",
+        "properties": {
+            "log_level": { "enum": LOG_LEVELS },
+            "display": reference("hotkey"),
+            "hide": reference("hotkey"),
+            "profile": {
+                "type": "string",
+                "description": "Name of the profile active when the tool starts.",
+            },
+            "cycle_profile": reference("hotkey"),
+            "dxgi_debug": { "type": "boolean" },
+            "show_console": { "type": "boolean" },
+            "disable_update_prompt": { "type": "boolean" },
+            "indicators": { "type": "array", "items": reference("indicator") },
+            "radial_menu_open": {
+                "type": "string",
+                "description": "Controller buttons separated by `+`, e.g. \"l3+r3\".",
+            },
+            "livesplit": {
+                "type": "object",
+                "properties": {
+                    "address": { "type": "string" },
+                    "split": reference("hotkey"),
+                    "reset": reference("hotkey"),
+                    "auto_reset": { "type": "boolean" },
+                },
+                "additionalProperties": false,
+            },
+            "api": {
+                "type": "object",
+                "properties": {
+                    "port": { "type": "integer", "minimum": 0, "maximum": 65535 },
+                    "token": { "type": "string", "minLength": 1 },
+                },
+                "required": ["token"],
+                "additionalProperties": false,
+            },
+            "export": {
+                "type": "object",
+                "properties": {
+                    "interval": { "type": "integer", "minimum": 0 },
+                    "files": {
+                        "type": "array",
+                        "items": {
+                            "type": "object",
+                            "properties": {
+                                "path": { "type": "string" },
+                                "template": { "type": "string" },
+                            },
+                            "required": ["path"],
+                            "additionalProperties": false,
+                        },
+                    },
+                },
+                "required": ["files"],
+                "additionalProperties": false,
+            },
+            "hit_count": {
+                "type": "object",
+                "properties": { "split_linked": { "type": "boolean" } },
+                "additionalProperties": false,
+            },
+            "animation_history": {
+                "type": "object",
+                "properties": { "length": { "type": "integer", "minimum": 0 } },
+                "additionalProperties": false,
+            },
+            "speedometer": {
+                "type": "object",
+                "properties": {
+                    "smoothing": { "type": "number", "minimum": 0 },
+                    "peak_hold": { "type": "number", "minimum": 0 },
+                },
+                "additionalProperties": false,
+            },
+        },
+        "required": ["log_level", "display"],
+        "additionalProperties": false,
+    })
+}
+
+fn radial_menu() -> Value {
+    json!({
+        "type": "array",
+        "items": {
+            "type": "object",
+            "properties": { "key": reference("hotkey"), "label": { "type": "string" } },
+            "required": ["key", "label"],
+            "additionalProperties": false,
+        },
+    })
+}
+
+fn profile() -> Value {
+    json!({
+        "type": "object",
+        "description": "Commands, radial menu and indicators selected in game. The ones left \
+                        out are taken from the top level.",
+        "properties": {
+            "name": { "type": "string", "not": { "const": DEFAULT_PROFILE } },
+            "radial-menu": reference("radial_menu"),
+            "indicators": { "type": "array", "items": reference("indicator") },
+            "commands": { "type": "array", "items": reference("command") },
+        },
+        "required": ["name"],
+        "additionalProperties": false,
+    })
+}
+
+/// The schema of `jdsd_er_practice_tool.toml`.
+pub fn schema() -> Value {
+    json!({
+        "$schema": "http://json-schema.org/draft-07/schema#",
+        "title": "Elden Ring Practice Tool configuration",
+        "type": "object",
+        "properties": {
+            "settings": reference("settings"),
+            "radial-menu": reference("radial_menu"),
+            "commands": { "type": "array", "items": reference("command") },
+            "profiles": { "type": "array", "items": reference("profile") },
+            "include": {
+                "type": "array",
+                "items": { "type": "string" },
+                "description": "Files whose commands, radial menu entries and groups are added \
+                                to this file's, relative to it.",
+            },
+            "groups": {
+                "type": "object",
+                "additionalProperties": { "type": "array", "items": reference("command") },
+                "description": "Groups of commands, added to `commands` as { group = \"Name\" }.",
+            },
+        },
+        "required": ["settings", "radial-menu", "commands"],
+        "definitions": {
+            "hotkey": {
+                "type": "string",
+                "pattern": HOTKEY_PATTERN,
+                "description": HOTKEY_DESCRIPTION,
+            },
+            "hotkey_or_true": {
+                "anyOf": [reference("hotkey"), { "const": true }],
+                "description": "A hotkey, or `true` to enable the command without one.",
+            },
+            "settings": settings(),
+            "indicator": indicator(),
+            "radial_menu": radial_menu(),
+            "profile": profile(),
+            "command": { "anyOf": SHAPES.iter().map(command).collect::<Vec<_>>() },
+        },
+    })
+}
+
+/// The schema as written to [`SCHEMA_FILE`].
+pub fn schema_string() -> String {
+    let mut schema = serde_json::to_string_pretty(&schema()).unwrap();
+    schema.push('\n');
+    schema
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::check::PROFILE_KEYS;
+    use crate::{Anchor, CfgCommand, Config, ValueType};
+
+    /// The shape each command is written with. Adding a variant to
+    /// `CfgCommand` fails to compile here until the schema covers it.
+    fn shape_key(command: &CfgCommand) -> &'static str {
+        match command {
+            CfgCommand::SavefileManager { .. } => "savefile_manager",
+            CfgCommand::ItemSpawner { .. } => "item_spawner",
+            CfgCommand::Flag { .. } | CfgCommand::MultiFlag { .. } => "flag",
+            CfgCommand::SpecialFlag { .. } => "flag",
+            CfgCommand::MultiFlagUser { .. } => "flags",
+            CfgCommand::Label { .. } => "label",
+            CfgCommand::Position { .. } => "position",
+            CfgCommand::NudgePosition { .. } => "nudge",
+            CfgCommand::CycleSpeed { .. } => "cycle_speed",
+            CfgCommand::CycleColor { .. } => "cycle_color",
+            CfgCommand::CharacterStats { .. } => "character_stats",
+            CfgCommand::Runes { .. } => "runes",
+            CfgCommand::Target { .. } => "target",
+            CfgCommand::DamageLog { .. } => "damage_log",
+            CfgCommand::FrameAdvance { .. } => "frame_advance",
+            CfgCommand::Warp { .. } => "warp",
+            CfgCommand::Group { .. } => "group",
+            CfgCommand::Quitout { .. } => "quitout",
+        }
+    }
+
+    #[test]
+    fn test_schema_up_to_date() {
+        let committed = include_str!("../../../jdsd_er_practice_tool.schema.json");
+        assert!(
+            committed.replace("\r\n", "\n") == schema_string(),
+            "{SCHEMA_FILE} is out of date, run `cargo xtask schema`"
+        );
+    }
+
+    #[test]
+    fn test_shapes_cover_commands() {
+        let config = Config::parse(
+            r#"radial-menu = []
+            commands = [
+                { savefile_manager = "ctrl+o" },
+                { item_spawner = true },
+                { flag = "no_damage" },
+                { flag = "show_map" },
+                { flag = "deathcam" },
+                { flags = ["collision", "torrent_collision"], label = "No Collision" },
+                { label = "Text" },
+                { position = "h", save = "rshift+h" },
+                { nudge = 1.0, nudge_up = "[", nudge_down = "]" },
+                { cycle_speed = [0.5, 1.0] },
+                { cycle_color = [0, 1] },
+                { character_stats = true },
+                { runes = 10000 },
+                { target = true, track = 4 },
+                { damage_log = true },
+                { frame_advance = "ctrl+f" },
+                { warp = true },
+                { group = "Group", commands = [] },
+                { quitout = "p" },
+            ]
+            [settings]
+            log_level = "INFO"
+            display = "0"
+            "#,
+        )
+        .unwrap();
+
+        let mut keys = config.commands.iter().map(|c| shape_key(&c.command)).collect::<Vec<_>>();
+        keys.sort_unstable();
+        keys.dedup();
+        let mut shapes = SHAPES.iter().map(|shape| shape.key).collect::<Vec<_>>();
+        shapes.sort_unstable();
+        assert_eq!(keys, shapes);
+    }
+
+    #[test]
+    fn test_enum_names() {
+        for anchor in ANCHORS {
+            serde_json::from_value::<Anchor>(json!(anchor)).unwrap();
+        }
+        for value_type in VALUE_TYPES {
+            serde_json::from_value::<ValueType>(json!(value_type)).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_profile_keys() {
+        let schema = schema();
+        let properties = schema["definitions"]["profile"]["properties"].as_object().unwrap();
+        let mut keys = properties.keys().map(String::as_str).collect::<Vec<_>>();
+        keys.sort_unstable();
+        let mut expected = PROFILE_KEYS.to_vec();
+        expected.sort_unstable();
+        assert_eq!(keys, expected);
+    }
+
+    #[test]
+    fn test_shipped_settings() {
+        let schema = schema();
+        let properties = &schema["definitions"]["settings"]["properties"];
+        let cfg = include_str!("../../../jdsd_er_practice_tool.toml").parse::<toml::Value>();
+        for key in cfg.unwrap()["settings"].as_table().unwrap().keys() {
+            assert!(properties.get(key).is_some(), "settings.{key} is missing from the schema");
+        }
+    }
+}
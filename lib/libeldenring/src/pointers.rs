@@ -104,6 +104,11 @@ pub struct Pointers {
     pub show_all_map_layers: Bitflag<u8>,
     pub show_all_graces: Bitflag<u8>,
 
+    // Version-dependent WorldChrMan offsets
+    pub net_players_ins: usize,
+    pub player_ins: usize,
+    pub torrent_enemy_ins: usize,
+
     pub base_addresses: BaseAddresses,
 }
 
@@ -518,6 +523,9 @@ impl Pointers {
             current_target: pointer_chain!(current_target),
             show_all_map_layers: bitflag!(0b1; func_check_graces),
             show_all_graces: bitflag!(0b1; func_check_graces + 0x1),
+            net_players_ins,
+            player_ins,
+            torrent_enemy_ins,
             base_addresses,
         }
     }
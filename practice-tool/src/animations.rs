@@ -0,0 +1,107 @@
+//! History of the player's recent animations.
+
+use std::collections::VecDeque;
+
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct AnimationEntry {
+    pub(crate) anim: u32,
+    /// IGT in milliseconds when the animation started.
+    pub(crate) start: usize,
+    /// Milliseconds the animation played for. Grows while it is current.
+    pub(crate) duration: usize,
+}
+
+#[derive(Debug)]
+pub(crate) struct AnimationHistory {
+    entries: VecDeque<AnimationEntry>,
+    capacity: usize,
+}
+
+impl AnimationHistory {
+    pub(crate) fn new(capacity: usize) -> Self {
+        AnimationHistory { entries: VecDeque::with_capacity(capacity), capacity: capacity.max(1) }
+    }
+
+    /// Feed the current animation and IGT. Call once per frame.
+    pub(crate) fn update(&mut self, anim: Option<u32>, igt: Option<usize>) {
+        let (Some(anim), Some(igt)) = (anim, igt) else {
+            return;
+        };
+
+        // The IGT went backwards: a new character was loaded, or the game restarted.
+        if self.entries.back().is_some_and(|last| igt < last.start) {
+            self.entries.clear();
+        }
+
+        match self.entries.back_mut() {
+            Some(last) if last.anim == anim => last.duration = igt - last.start,
+            _ => {
+                if self.entries.len() >= self.capacity {
+                    self.entries.pop_front();
+                }
+                self.entries.push_back(AnimationEntry { anim, start: igt, duration: 0 });
+            },
+        }
+    }
+
+    /// Entries, most recent first.
+    pub(crate) fn entries(&self) -> impl Iterator<Item = &AnimationEntry> {
+        self.entries.iter().rev()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entries(history: &AnimationHistory) -> Vec<(u32, usize, usize)> {
+        history.entries().map(|entry| (entry.anim, entry.start, entry.duration)).collect()
+    }
+
+    #[test]
+    fn test_dedupe() {
+        let mut history = AnimationHistory::new(4);
+
+        history.update(Some(100), Some(1000));
+        history.update(Some(100), Some(1016));
+        history.update(Some(100), Some(1033));
+        assert_eq!(entries(&history), [(100, 1000, 33)]);
+
+        history.update(Some(200), Some(1050));
+        history.update(Some(200), Some(1100));
+        // The same animation again after another one is a new entry.
+        history.update(Some(100), Some(1116));
+        assert_eq!(entries(&history), [(100, 1116, 0), (200, 1050, 50), (100, 1000, 33)]);
+
+        // Frames without an animation or IGT are skipped.
+        history.update(None, Some(1200));
+        history.update(Some(300), None);
+        assert_eq!(entries(&history).len(), 3);
+    }
+
+    #[test]
+    fn test_capacity() {
+        let mut history = AnimationHistory::new(3);
+        for (i, anim) in [1, 2, 3, 4, 5].into_iter().enumerate() {
+            history.update(Some(anim), Some(i * 100));
+        }
+        let anims = history.entries().map(|entry| entry.anim).collect::<Vec<_>>();
+        assert_eq!(anims, [5, 4, 3]);
+
+        let mut history = AnimationHistory::new(0);
+        history.update(Some(1), Some(0));
+        history.update(Some(2), Some(100));
+        assert_eq!(entries(&history), [(2, 100, 0)]);
+    }
+
+    #[test]
+    fn test_igt_reset() {
+        let mut history = AnimationHistory::new(4);
+        history.update(Some(1), Some(5000));
+        history.update(Some(2), Some(6000));
+
+        // Loading another character starts over.
+        history.update(Some(2), Some(100));
+        assert_eq!(entries(&history), [(2, 100, 0)]);
+    }
+}
@@ -0,0 +1,623 @@
+//! Opt-in local control API.
+//!
+//! Listens on localhost for newline-delimited JSON requests and answers each
+//! of them with a single line of JSON, for example:
+//!
+//! ```text
+//! > {"token": "secret", "cmd": "list"}
+//! < {"ok":true,"data":[{"id":0,"kind":"flag","label":"All no damage","state":false}]}
+//! > {"token": "secret", "cmd": "set_flag", "id": 0, "state": true}
+//! < {"ok":true}
+//! > {"token": "secret", "cmd": "warp", "grace": "Gatefront"}
+//! < {"ok":true,"data":{"grace":"[Limgrave] Gatefront"}}
+//! ```
+//!
+//! Every request carries the token from the settings. The connection is
+//! closed on the first line that isn't a valid request, or that looks like
+//! HTTP, so web pages can't drive the tool through the port.
+//!
+//! Requests are forwarded to the render loop, which owns the widgets and
+//! dispatches into the same objects the UI and hotkeys use. Warping and
+//! spawning items need a `warp` and an `item_spawner` command in the config.
+//!
+//! This is a plain TCP socket rather than a WebSocket, so that `nc` or a few
+//! lines of any scripting language can talk to it. Browser-based tools need a
+//! bridge such as `websocat`.
+
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{Ipv4Addr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use hudhook::tracing::{debug, error, info};
+use libeldenring::prelude::*;
+use practice_tool_core::crossbeam_channel::{self, Receiver, Sender};
+use practice_tool_core::widgets::Widget;
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::widgets::item_spawn::ItemSpawner;
+use crate::widgets::target::{locked_on_entity, EntityPointerChains};
+use crate::widgets::warp::{find_grace, Warp};
+
+const RESPONSE_TIMEOUT: Duration = Duration::from_secs(2);
+
+type Envelope = (Request, Sender<Value>);
+
+/// Parameters for the next action of a widget, set through the API.
+type Pending<T> = Arc<Mutex<Option<T>>>;
+
+#[derive(Debug, Deserialize)]
+struct Message {
+    token: String,
+    #[serde(flatten)]
+    request: Request,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "cmd", rename_all = "snake_case")]
+pub(crate) enum Request {
+    List,
+    Action {
+        id: usize,
+    },
+    SetFlag {
+        id: usize,
+        state: bool,
+    },
+    Quitout,
+    Warp {
+        grace: String,
+    },
+    SpawnItem {
+        item_id: u32,
+        #[serde(default = "default_qty")]
+        qty: u32,
+    },
+    Indicators,
+}
+
+fn default_qty() -> u32 {
+    1
+}
+
+struct ControlEntry {
+    label: String,
+    flags: Vec<Box<dyn FlagToggler>>,
+    trigger: Arc<AtomicBool>,
+}
+
+/// Widgets that can be driven through the API, in the order they were built.
+#[derive(Default)]
+pub(crate) struct ControlRegistry {
+    entries: Vec<ControlEntry>,
+    warp: Option<Pending<usize>>,
+    spawn_item: Option<Pending<(u32, u32)>>,
+}
+
+impl ControlRegistry {
+    /// Register a widget and return it wrapped so that it runs its action
+    /// when triggered through the API. Flags that back the widget, if any,
+    /// can be read and set directly.
+    pub(crate) fn register(
+        &mut self,
+        label: String,
+        flags: Vec<Box<dyn FlagToggler>>,
+        widget: Box<dyn Widget>,
+    ) -> Box<dyn Widget> {
+        let trigger = Arc::new(AtomicBool::new(false));
+        self.entries.push(ControlEntry { label, flags, trigger: Arc::clone(&trigger) });
+        Box::new(Controlled { widget, trigger })
+    }
+
+    /// Register the grace warp widget, which warps to the graces requested
+    /// through the API the next time it's interacted with.
+    pub(crate) fn register_warp(&mut self, widget: Warp) -> Box<dyn Widget> {
+        let (widget, pending) = with_params(widget, Warp::warp_to_grace);
+        self.warp = Some(pending);
+        widget
+    }
+
+    /// Register the item spawner widget, which spawns the items requested
+    /// through the API the next time it's interacted with.
+    pub(crate) fn register_item_spawner(
+        &mut self,
+        widget: ItemSpawner<'static>,
+    ) -> Box<dyn Widget> {
+        let (widget, pending) =
+            with_params(widget, |widget, (item_id, qty)| widget.spawn_item(item_id, qty));
+        self.spawn_item = Some(pending);
+        widget
+    }
+
+    fn entry(&self, id: usize) -> Result<&ControlEntry, String> {
+        self.entries.get(id).ok_or_else(|| format!("No command with id {id}"))
+    }
+
+    fn list(&self) -> Value {
+        self.entries
+            .iter()
+            .enumerate()
+            .map(|(id, entry)| {
+                json!({
+                    "id": id,
+                    "kind": if entry.flags.is_empty() { "action" } else { "flag" },
+                    "label": entry.label,
+                    "state": entry.flags.first().and_then(|flag| flag.get()),
+                })
+            })
+            .collect()
+    }
+
+    /// Run the action of a widget the next time it's interacted with.
+    fn trigger(&self, id: usize) -> Result<(), String> {
+        self.entry(id)?.trigger.store(true, Ordering::Relaxed);
+        Ok(())
+    }
+
+    fn set_flag(&self, id: usize, state: bool) -> Result<(), String> {
+        let entry = self.entry(id)?;
+        if entry.flags.is_empty() {
+            return Err(format!("\"{}\" is not a flag", entry.label));
+        }
+        entry.flags.iter().for_each(|flag| flag.set(state));
+        Ok(())
+    }
+
+    fn warp(&self, grace: &str) -> Result<Value, String> {
+        let pending =
+            self.warp.as_ref().ok_or_else(|| "No warp command in the config".to_string())?;
+        let (index, name) = find_grace(grace).ok_or_else(|| format!("No grace matches {grace}"))?;
+        set_pending(pending, index);
+        Ok(json!({ "grace": name }))
+    }
+
+    fn spawn_item(&self, item_id: u32, qty: u32) -> Result<(), String> {
+        let pending = self
+            .spawn_item
+            .as_ref()
+            .ok_or_else(|| "No item_spawner command in the config".to_string())?;
+        set_pending(pending, (item_id, qty));
+        Ok(())
+    }
+}
+
+fn set_pending<T>(pending: &Pending<T>, params: T) {
+    if let Ok(mut pending) = pending.lock() {
+        *pending = Some(params);
+    }
+}
+
+fn with_params<W: Widget + 'static, P: Send + 'static>(
+    widget: W,
+    apply: fn(&mut W, P),
+) -> (Box<dyn Widget>, Pending<P>) {
+    let pending = Pending::default();
+    (Box::new(WithParams { widget, pending: Arc::clone(&pending), apply }), pending)
+}
+
+struct Controlled {
+    widget: Box<dyn Widget>,
+    trigger: Arc<AtomicBool>,
+}
+
+impl std::fmt::Debug for Controlled {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Controlled {{ trigger: {:?} }}", self.trigger)
+    }
+}
+
+impl Widget for Controlled {
+    fn render(&mut self, ui: &imgui::Ui) {
+        self.widget.render(ui);
+    }
+
+    fn render_closed(&mut self, ui: &imgui::Ui) {
+        self.widget.render_closed(ui);
+    }
+
+    fn interact(&mut self, ui: &imgui::Ui) {
+        if self.trigger.swap(false, Ordering::Relaxed) {
+            self.widget.action();
+        }
+
+        self.widget.interact(ui);
+    }
+
+    fn action(&mut self) {
+        self.widget.action();
+    }
+
+    fn log(&mut self, tx: Sender<String>) {
+        self.widget.log(tx);
+    }
+}
+
+/// Widget whose action takes parameters set through the API.
+struct WithParams<W, P> {
+    widget: W,
+    pending: Pending<P>,
+    apply: fn(&mut W, P),
+}
+
+impl<W: Widget, P> std::fmt::Debug for WithParams<W, P> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "WithParams {{ widget: {:?} }}", self.widget)
+    }
+}
+
+impl<W: Widget, P: Send> Widget for WithParams<W, P> {
+    fn render(&mut self, ui: &imgui::Ui) {
+        self.widget.render(ui);
+    }
+
+    fn render_closed(&mut self, ui: &imgui::Ui) {
+        self.widget.render_closed(ui);
+    }
+
+    fn interact(&mut self, ui: &imgui::Ui) {
+        if let Some(params) = self.pending.lock().ok().and_then(|mut pending| pending.take()) {
+            (self.apply)(&mut self.widget, params);
+        }
+
+        self.widget.interact(ui);
+    }
+
+    fn action(&mut self) {
+        self.widget.action();
+    }
+
+    fn log(&mut self, tx: Sender<String>) {
+        self.widget.log(tx);
+    }
+}
+
+pub(crate) struct Api {
+    rx: Receiver<Envelope>,
+}
+
+impl Api {
+    pub(crate) fn new(port: u16, token: String) -> Self {
+        let (tx, rx) = crossbeam_channel::unbounded();
+
+        thread::Builder::new()
+            .name("api".to_string())
+            .spawn(move || listen(port, token, tx))
+            .map_err(|e| error!("Couldn't start control API thread: {e}"))
+            .ok();
+
+        Api { rx }
+    }
+
+    /// Answer the pending requests. Must run on the render thread.
+    pub(crate) fn process(&self, registry: &ControlRegistry, pointers: &Pointers) {
+        for (request, tx) in self.rx.try_iter() {
+            let response = match handle(request, registry, pointers) {
+                Ok(Value::Null) => json!({ "ok": true }),
+                Ok(data) => json!({ "ok": true, "data": data }),
+                Err(e) => failure(e),
+            };
+            tx.send(response).ok();
+        }
+    }
+}
+
+fn handle(
+    request: Request,
+    registry: &ControlRegistry,
+    pointers: &Pointers,
+) -> Result<Value, String> {
+    match request {
+        Request::List => Ok(registry.list()),
+        Request::Action { id } => registry.trigger(id).map(|()| Value::Null),
+        Request::SetFlag { id, state } => registry.set_flag(id, state).map(|()| Value::Null),
+        Request::Quitout => {
+            pointers.quitout.write(1).ok_or_else(|| "Couldn't quit out".to_string())?;
+            Ok(Value::Null)
+        },
+        Request::Warp { grace } => registry.warp(&grace),
+        Request::SpawnItem { item_id, qty } => {
+            registry.spawn_item(item_id, qty).map(|()| Value::Null)
+        },
+        Request::Indicators => {
+            let position = pointers.global_position.read().map(|[x, y, z, _, _]| {
+                json!({ "x": x, "y": y, "z": z, "map_id": pointers.global_position.read_map_id() })
+            });
+            let target = locked_on_entity()
+                .and_then(|addr| EntityPointerChains::new(addr).hp.read())
+                .map(|[hp, _, max_hp]| json!({ "hp": hp, "max_hp": max_hp }));
+
+            Ok(json!({ "igt": pointers.igt.read(), "position": position, "target": target }))
+        },
+    }
+}
+
+fn failure<S: AsRef<str>>(error: S) -> Value {
+    json!({ "ok": false, "error": error.as_ref() })
+}
+
+fn listen(port: u16, token: String, tx: Sender<Envelope>) {
+    if token.is_empty() {
+        error!("Not starting control API without a token");
+        return;
+    }
+
+    let listener = match TcpListener::bind((Ipv4Addr::LOCALHOST, port)) {
+        Ok(listener) => listener,
+        Err(e) => {
+            error!("Couldn't start control API on port {port}: {e}");
+            return;
+        },
+    };
+
+    info!("Control API listening on 127.0.0.1:{port}");
+
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(e) => {
+                error!("Control API connection failed: {e}");
+                continue;
+            },
+        };
+
+        let token = token.clone();
+        let tx = tx.clone();
+        thread::spawn(move || {
+            if let Err(e) = serve(stream, &token, tx) {
+                debug!("Control API client disconnected: {e}");
+            }
+        });
+    }
+}
+
+fn serve(stream: TcpStream, token: &str, tx: Sender<Envelope>) -> io::Result<()> {
+    let mut writer = stream.try_clone()?;
+
+    for line in BufReader::new(stream).lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        if looks_like_http(&line) {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "HTTP request"));
+        }
+
+        let request = match parse(&line, token) {
+            Ok(request) => request,
+            Err(e) => {
+                writeln!(writer, "{}", failure(&e))?;
+                return Err(io::Error::new(io::ErrorKind::InvalidData, e));
+            },
+        };
+
+        let (response_tx, response_rx) = crossbeam_channel::bounded(1);
+        if tx.send((request, response_tx)).is_err() {
+            break;
+        }
+        let response = response_rx
+            .recv_timeout(RESPONSE_TIMEOUT)
+            .unwrap_or_else(|_| failure("Timed out waiting for the game"));
+
+        writeln!(writer, "{response}")?;
+    }
+
+    Ok(())
+}
+
+fn parse(line: &str, token: &str) -> Result<Request, String> {
+    let message =
+        serde_json::from_str::<Message>(line).map_err(|e| format!("Invalid request: {e}"))?;
+    if message.token != token {
+        return Err("Invalid token".to_string());
+    }
+    Ok(message.request)
+}
+
+/// Whether the line is the request line of an HTTP request, e.g. a web page
+/// posting to the port.
+fn looks_like_http(line: &str) -> bool {
+    line.split_whitespace().last().is_some_and(|version| version.starts_with("HTTP/"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Clone, Debug, Default)]
+    struct TestFlag(Arc<AtomicBool>);
+
+    impl FlagToggler for TestFlag {
+        fn clone_box(&self) -> Box<dyn FlagToggler> {
+            Box::new(self.clone())
+        }
+
+        fn toggle(&self) -> Option<bool> {
+            Some(!self.0.fetch_xor(true, Ordering::Relaxed))
+        }
+
+        fn get(&self) -> Option<bool> {
+            Some(self.0.load(Ordering::Relaxed))
+        }
+
+        fn set(&self, flag: bool) {
+            self.0.store(flag, Ordering::Relaxed);
+        }
+    }
+
+    #[derive(Debug)]
+    struct TestWidget;
+
+    impl Widget for TestWidget {
+        fn render(&mut self, _ui: &imgui::Ui) {}
+    }
+
+    fn registry(flags: &[TestFlag]) -> ControlRegistry {
+        let mut registry = ControlRegistry::default();
+        registry.register("Quitout".to_string(), Vec::new(), Box::new(TestWidget));
+        let flags = flags.iter().map(|flag| flag.clone_box()).collect();
+        registry.register("No damage".to_string(), flags, Box::new(TestWidget));
+        registry
+    }
+
+    #[test]
+    fn test_request() {
+        let parse = |s| serde_json::from_str::<Request>(s).map_err(|e| e.to_string());
+
+        assert!(matches!(parse(r#"{"cmd": "list"}"#), Ok(Request::List)));
+        assert!(matches!(parse(r#"{"cmd": "action", "id": 3}"#), Ok(Request::Action { id: 3 })));
+        assert!(matches!(
+            parse(r#"{"cmd": "set_flag", "id": 1, "state": true}"#),
+            Ok(Request::SetFlag { id: 1, state: true })
+        ));
+        assert!(matches!(
+            parse(r#"{"cmd": "warp", "grace": "Gatefront"}"#),
+            Ok(Request::Warp { grace }) if grace == "Gatefront"
+        ));
+        assert!(matches!(
+            parse(r#"{"cmd": "spawn_item", "item_id": 1000}"#),
+            Ok(Request::SpawnItem { item_id: 1000, qty: 1 })
+        ));
+        assert!(matches!(
+            parse(r#"{"cmd": "spawn_item", "item_id": 1000, "qty": 5}"#),
+            Ok(Request::SpawnItem { item_id: 1000, qty: 5 })
+        ));
+        assert!(matches!(parse(r#"{"cmd": "quitout"}"#), Ok(Request::Quitout)));
+        assert!(matches!(parse(r#"{"cmd": "indicators"}"#), Ok(Request::Indicators)));
+
+        assert!(parse(r#"{"cmd": "fly"}"#).unwrap_err().contains("unknown variant `fly`"));
+        assert!(parse(r#"{"cmd": "set_flag", "id": 1}"#).unwrap_err().contains("`state`"));
+        assert!(parse(r#"{"id": 1}"#).is_err());
+    }
+
+    #[test]
+    fn test_list() {
+        let flag = TestFlag::default();
+        let registry = registry(std::slice::from_ref(&flag));
+
+        assert_eq!(
+            registry.list(),
+            json!([
+                { "id": 0, "kind": "action", "label": "Quitout", "state": null },
+                { "id": 1, "kind": "flag", "label": "No damage", "state": false },
+            ])
+        );
+
+        flag.set(true);
+        assert_eq!(registry.list()[1]["state"], json!(true));
+    }
+
+    #[test]
+    fn test_trigger() {
+        let registry = registry(&[]);
+
+        registry.trigger(0).unwrap();
+        assert!(registry.entries[0].trigger.load(Ordering::Relaxed));
+        assert!(!registry.entries[1].trigger.load(Ordering::Relaxed));
+        assert_eq!(registry.trigger(2), Err("No command with id 2".to_string()));
+    }
+
+    #[test]
+    fn test_set_flag() {
+        let flags = [TestFlag::default(), TestFlag::default()];
+        let registry = registry(&flags);
+
+        registry.set_flag(1, true).unwrap();
+        assert!(flags.iter().all(|flag| flag.get() == Some(true)));
+        registry.set_flag(1, false).unwrap();
+        assert!(flags.iter().all(|flag| flag.get() == Some(false)));
+
+        assert_eq!(registry.set_flag(0, true), Err("\"Quitout\" is not a flag".to_string()));
+        assert_eq!(registry.set_flag(5, true), Err("No command with id 5".to_string()));
+    }
+
+    #[test]
+    fn test_spawn_item() {
+        let mut registry = registry(&[]);
+        assert_eq!(
+            registry.spawn_item(1000, 5),
+            Err("No item_spawner command in the config".to_string())
+        );
+        assert_eq!(registry.warp("Gatefront"), Err("No warp command in the config".to_string()));
+
+        let pending = Pending::default();
+        registry.spawn_item = Some(Arc::clone(&pending));
+        registry.spawn_item(1000, 5).unwrap();
+        assert_eq!(pending.lock().unwrap().take(), Some((1000, 5)));
+    }
+
+    #[test]
+    fn test_parse() {
+        assert!(matches!(
+            parse(r#"{"token": "secret", "cmd": "list"}"#, "secret"),
+            Ok(Request::List)
+        ));
+        assert!(matches!(
+            parse(r#"{"token": "secret", "cmd": "action", "id": 3}"#, "secret"),
+            Ok(Request::Action { id: 3 })
+        ));
+        assert_eq!(
+            parse(r#"{"token": "wrong", "cmd": "list"}"#, "secret").unwrap_err(),
+            "Invalid token"
+        );
+        assert!(parse(r#"{"cmd": "list"}"#, "secret").unwrap_err().contains("`token`"));
+        assert!(parse("list", "secret").unwrap_err().starts_with("Invalid request"));
+    }
+
+    #[test]
+    fn test_looks_like_http() {
+        assert!(looks_like_http("POST / HTTP/1.1"));
+        assert!(looks_like_http("GET /?cmd=list HTTP/1.0\r"));
+        assert!(!looks_like_http(r#"{"token": "HTTP/1.1", "cmd": "list"}"#));
+    }
+
+    /// Connect to `serve`, with every forwarded request answered by `ok`.
+    fn connect() -> (TcpStream, BufReader<TcpStream>) {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+        let client = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        let (stream, _) = listener.accept().unwrap();
+
+        let (tx, rx) = crossbeam_channel::unbounded::<Envelope>();
+        thread::spawn(move || serve(stream, "secret", tx));
+        thread::spawn(move || {
+            for (_, tx) in rx {
+                tx.send(json!({ "ok": true })).ok();
+            }
+        });
+
+        let reader = BufReader::new(client.try_clone().unwrap());
+        (client, reader)
+    }
+
+    fn read_line(reader: &mut BufReader<TcpStream>) -> String {
+        let mut line = String::new();
+        reader.read_line(&mut line).ok();
+        line
+    }
+
+    #[test]
+    fn test_serve() {
+        let (mut client, mut reader) = connect();
+        writeln!(client, r#"{{"token": "secret", "cmd": "list"}}"#).unwrap();
+        assert_eq!(read_line(&mut reader), "{\"ok\":true}\n");
+        writeln!(client).unwrap();
+        writeln!(client, r#"{{"token": "secret", "cmd": "list"}}"#).unwrap();
+        assert_eq!(read_line(&mut reader), "{\"ok\":true}\n");
+
+        writeln!(client, r#"{{"token": "wrong", "cmd": "list"}}"#).unwrap();
+        assert_eq!(read_line(&mut reader), format!("{}\n", failure("Invalid token")));
+        assert_eq!(read_line(&mut reader), "");
+
+        let (mut client, mut reader) = connect();
+        writeln!(client, r#"{{"cmd": "list"}}"#).unwrap();
+        assert!(read_line(&mut reader).contains("Invalid request"));
+        assert_eq!(read_line(&mut reader), "");
+
+        let (mut client, mut reader) = connect();
+        write!(client, "POST / HTTP/1.1\r\nHost: localhost\r\n\r\n").unwrap();
+        assert_eq!(read_line(&mut reader), "");
+    }
+}
@@ -0,0 +1,733 @@
+//! Format-preserving edits to the config file.
+//!
+//! Changes made in game are written back with `toml_edit`, which keeps the
+//! user's comments, ordering and whitespace intact around the edited values.
+
+use std::path::Path;
+
+use practice_tool_core::key::Key;
+use toml_edit::{Array, DocumentMut, InlineTable, Item, RawString, Table, TableLike, Value};
+
+use crate::config::Indicator;
+
+/// Keys holding the list of values of cycling commands.
+const FLOAT_LISTS: &[&str] = &["cycle_speed"];
+const INT_LISTS: &[&str] = &["cycle_color"];
+
+/// Commands whose first key holds either their hotkey or `true`.
+const PLACEHOLDER_HOTKEYS: &[&str] = &[
+    "savefile_manager",
+    "item_spawner",
+    "position",
+    "character_stats",
+    "target",
+    "damage_log",
+    "frame_advance",
+    "quitout",
+];
+
+/// Commands that accept an optional `hotkey` even when none is set.
+const OPTIONAL_HOTKEY: &[&str] = &["flag", "flags", "cycle_speed", "cycle_color", "runes"];
+
+/// Hotkeys of the `[settings]` table.
+const SETTINGS_HOTKEYS: &[&str] = &["display", "hide", "cycle_profile"];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum FieldKind {
+    Hotkey,
+    Floats,
+    Ints,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Location {
+    Settings,
+    /// Indices in the `commands` array or `[[commands]]` tables, then in the
+    /// `commands` of each group.
+    Command(Vec<usize>),
+    /// Index in `profiles`, then indices as in `Command`.
+    ProfileCommand(usize, Vec<usize>),
+}
+
+/// A value that can be edited in game.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Field {
+    pub(crate) location: Location,
+    pub(crate) key: String,
+    pub(crate) label: String,
+    pub(crate) kind: FieldKind,
+    pub(crate) value: String,
+}
+
+#[derive(Debug)]
+pub(crate) struct ConfigDocument {
+    doc: DocumentMut,
+}
+
+fn is_hotkey(value: &str) -> bool {
+    toml::Value::String(value.to_string()).try_into::<Key>().is_ok()
+}
+
+/// Replace a value, keeping the whitespace and comments around it.
+fn replace(slot: &mut Value, value: impl Into<Value>) {
+    let decor = slot.decor().clone();
+    *slot = value.into();
+    *slot.decor_mut() = decor;
+}
+
+/// Append a key to an inline table, moving the space before the closing
+/// brace after the new value.
+fn append(table: &mut InlineTable, key: &str, value: Value) {
+    let mut suffix = RawString::from(" ");
+    if let Some((_, last)) = table.iter_mut().last() {
+        suffix = last.decor().suffix().cloned().unwrap_or_default();
+        last.decor_mut().set_suffix("");
+    }
+    table.insert(key, value.decorated(" ", suffix));
+}
+
+/// Remove a key from an inline table, keeping the space before the closing
+/// brace if it was the last one.
+fn remove(table: &mut InlineTable, key: &str) {
+    let is_last = table.iter().last().is_some_and(|(last, _)| last == key);
+    let Some(removed) = table.remove(key) else {
+        return;
+    };
+    if is_last {
+        let suffix = removed.decor().suffix().cloned().unwrap_or_default();
+        if let Some((_, last)) = table.iter_mut().last() {
+            last.decor_mut().set_suffix(suffix);
+        }
+    }
+}
+
+fn list_text(array: &Array) -> String {
+    array.iter().map(|v| v.clone().decorated("", "").to_string()).collect::<Vec<_>>().join(", ")
+}
+
+fn parse_list<T: std::str::FromStr>(text: &str) -> Result<Vec<T>, String> {
+    text.split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(|s| s.parse().map_err(|_| format!("Invalid value {s:?}")))
+        .collect()
+}
+
+/// Short description of a command, e.g. `flag = "no_damage"`.
+fn describe(table: &dyn TableLike) -> String {
+    if let Some(label) = table.get("label").and_then(Item::as_str) {
+        return label.to_string();
+    }
+
+    table
+        .iter()
+        .next()
+        .and_then(|(key, value)| {
+            Some(format!("{key} = {}", value.as_value()?.clone().decorated("", "")))
+        })
+        .unwrap_or_default()
+}
+
+/// The commands of a `commands` key with their indices: an array of inline
+/// tables, or `[[commands]]` tables.
+fn commands(item: &Item) -> Vec<(usize, &dyn TableLike)> {
+    match item {
+        Item::ArrayOfTables(commands) => {
+            commands.iter().map(|command| command as &dyn TableLike).enumerate().collect()
+        },
+        Item::Value(Value::Array(commands)) => commands
+            .iter()
+            .enumerate()
+            .filter_map(|(i, command)| Some((i, command.as_inline_table()? as &dyn TableLike)))
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+fn collect_fields(
+    commands_item: &Item,
+    location: &dyn Fn(Vec<usize>) -> Location,
+    path: &[usize],
+    prefix: &str,
+    fields: &mut Vec<Field>,
+) {
+    for (i, table) in commands(commands_item) {
+        let mut path = path.to_vec();
+        path.push(i);
+
+        if let Some(group) = table.get("group").and_then(Item::as_str) {
+            if let Some(commands) = table.get("commands") {
+                let prefix = format!("{prefix}{group} / ");
+                collect_fields(commands, location, &path, &prefix, fields);
+            }
+            continue;
+        }
+
+        let label = format!("{prefix}{}", describe(table));
+        let field = |key: &str, kind, value| Field {
+            location: location(path.clone()),
+            key: key.to_string(),
+            label: label.clone(),
+            kind,
+            value,
+        };
+
+        for (key, value) in table.iter() {
+            let Some(value) = value.as_value() else {
+                continue;
+            };
+            match value {
+                Value::String(s) if key != "label" && is_hotkey(s.value()) => {
+                    fields.push(field(key, FieldKind::Hotkey, s.value().clone()));
+                },
+                Value::Boolean(_) if PLACEHOLDER_HOTKEYS.contains(&key) => {
+                    fields.push(field(key, FieldKind::Hotkey, String::new()));
+                },
+                Value::Array(array) if FLOAT_LISTS.contains(&key) => {
+                    fields.push(field(key, FieldKind::Floats, list_text(array)));
+                },
+                Value::Array(array) if INT_LISTS.contains(&key) => {
+                    fields.push(field(key, FieldKind::Ints, list_text(array)));
+                },
+                _ => {},
+            }
+        }
+
+        let accepts_hotkey = table.iter().any(|(key, _)| OPTIONAL_HOTKEY.contains(&key));
+        if accepts_hotkey && !table.contains_key("hotkey") {
+            fields.push(field("hotkey", FieldKind::Hotkey, String::new()));
+        }
+    }
+}
+
+/// A command written as an inline table or as a `[[commands]]` table.
+enum CommandMut<'a> {
+    Inline(&'a mut InlineTable),
+    Table(&'a mut Table),
+}
+
+impl CommandMut<'_> {
+    fn set(&mut self, key: &str, value: Value) {
+        match self {
+            CommandMut::Inline(table) => match table.get_mut(key) {
+                Some(slot) => replace(slot, value),
+                None => append(table, key, value),
+            },
+            CommandMut::Table(table) => match table.get_mut(key).and_then(Item::as_value_mut) {
+                Some(slot) => replace(slot, value),
+                None => {
+                    table.insert(key, Item::Value(value));
+                },
+            },
+        }
+    }
+
+    fn remove(&mut self, key: &str) {
+        match self {
+            CommandMut::Inline(table) => remove(table, key),
+            CommandMut::Table(table) => {
+                table.remove(key);
+            },
+        }
+    }
+}
+
+fn command_mut<'a>(commands: &'a mut Item, path: &[usize]) -> Option<CommandMut<'a>> {
+    let (first, rest) = path.split_first()?;
+    match commands {
+        Item::ArrayOfTables(commands) => {
+            let table = commands.get_mut(*first)?;
+            match rest {
+                [] => Some(CommandMut::Table(table)),
+                _ => command_mut(table.get_mut("commands")?, rest),
+            }
+        },
+        Item::Value(Value::Array(commands)) => {
+            let mut table = commands.get_mut(*first)?.as_inline_table_mut()?;
+            for i in rest {
+                table = table
+                    .get_mut("commands")?
+                    .as_array_mut()?
+                    .get_mut(*i)?
+                    .as_inline_table_mut()?;
+            }
+            Some(CommandMut::Inline(table))
+        },
+        _ => None,
+    }
+}
+
+/// A multi-line `indicators` array listing `indicators`.
+fn indicator_list(indicators: &[Indicator]) -> Array {
+    let mut array = indicators
+        .iter()
+        .map(|indicator| {
+            let mut table = InlineTable::new();
+            table.insert("indicator", indicator.indicator.name().into());
+            table.insert("enabled", indicator.enabled.into());
+            Value::from(table).decorated("\n  ", "")
+        })
+        .collect::<Array>();
+    array.set_trailing_comma(true);
+    array.set_trailing("\n");
+    array
+}
+
+/// The `[[profiles]]` tables, also accepted as an array of inline tables.
+fn profiles(doc: &DocumentMut) -> Vec<&dyn TableLike> {
+    match doc.get("profiles") {
+        Some(Item::ArrayOfTables(profiles)) => {
+            profiles.iter().map(|profile| profile as &dyn TableLike).collect()
+        },
+        Some(Item::Value(Value::Array(profiles))) => profiles
+            .iter()
+            .filter_map(|profile| Some(profile.as_inline_table()? as &dyn TableLike))
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+fn profile_mut(doc: &mut DocumentMut, index: usize) -> Option<&mut dyn TableLike> {
+    match doc.get_mut("profiles")? {
+        Item::ArrayOfTables(profiles) => Some(profiles.get_mut(index)?),
+        Item::Value(Value::Array(profiles)) => {
+            Some(profiles.get_mut(index)?.as_inline_table_mut()?)
+        },
+        _ => None,
+    }
+}
+
+impl ConfigDocument {
+    pub(crate) fn parse(content: &str) -> Result<Self, String> {
+        content
+            .parse::<DocumentMut>()
+            .map(|doc| ConfigDocument { doc })
+            .map_err(|e| format!("Couldn't parse config file: {e}"))
+    }
+
+    pub(crate) fn load(path: &Path) -> Result<Self, String> {
+        let content =
+            std::fs::read_to_string(path).map_err(|e| format!("Couldn't read config file: {e}"))?;
+        Self::parse(&content)
+    }
+
+    pub(crate) fn save(&self, path: &Path) -> Result<(), String> {
+        std::fs::write(path, self.doc.to_string())
+            .map_err(|e| format!("Couldn't write config file: {e}"))
+    }
+
+    /// Set `enabled` on the indicator at `index` among the ones shown with
+    /// `profile` active: its own `indicators`, or `settings.indicators` for
+    /// the top-level profile and profiles that don't define any. Without a
+    /// `settings.indicators` key, `indicators` are the defaults in use, and
+    /// are written out in full.
+    pub(crate) fn set_indicator_enabled(
+        &mut self,
+        profile: usize,
+        index: usize,
+        enabled: bool,
+        indicators: &[Indicator],
+    ) -> bool {
+        let profile = profile.checked_sub(1).filter(|&profile| {
+            profiles(&self.doc).get(profile).is_some_and(|p| p.contains_key("indicators"))
+        });
+        let indicators = match profile {
+            Some(profile) => {
+                profile_mut(&mut self.doc, profile).and_then(|p| p.get_mut("indicators"))
+            },
+            None => self.doc.get_mut("settings").and_then(Item::as_table_mut).map(|settings| {
+                settings
+                    .entry("indicators")
+                    .or_insert_with(|| Item::Value(indicator_list(indicators).into()))
+            }),
+        };
+
+        let indicator = indicators
+            .and_then(Item::as_array_mut)
+            .and_then(|indicators| indicators.get_mut(index))
+            .and_then(Value::as_inline_table_mut);
+
+        let Some(indicator) = indicator else {
+            return false;
+        };
+
+        match indicator.get_mut("enabled") {
+            Some(slot) => replace(slot, enabled),
+            None => append(indicator, "enabled", enabled.into()),
+        }
+        true
+    }
+
+    /// Set the profile active when the tool starts, or clear it to start with
+    /// the top-level one.
+    pub(crate) fn set_profile(&mut self, name: Option<&str>) -> bool {
+        let Some(settings) = self.doc.get_mut("settings").and_then(Item::as_table_mut) else {
+            return false;
+        };
+
+        match name {
+            Some(name) => match settings.get_mut("profile").and_then(Item::as_value_mut) {
+                Some(slot) => replace(slot, name),
+                None => {
+                    settings.insert("profile", Item::Value(name.into()));
+                },
+            },
+            None => {
+                settings.remove("profile");
+            },
+        }
+        true
+    }
+
+    /// Every hotkey and list of values that can be edited in game.
+    pub(crate) fn fields(&self) -> Vec<Field> {
+        let mut fields = Vec::new();
+
+        if let Some(settings) = self.doc.get("settings").and_then(Item::as_table) {
+            for &key in SETTINGS_HOTKEYS {
+                let value = settings.get(key).and_then(Item::as_str).unwrap_or_default();
+                fields.push(Field {
+                    location: Location::Settings,
+                    key: key.to_string(),
+                    label: "settings".to_string(),
+                    kind: FieldKind::Hotkey,
+                    value: value.to_string(),
+                });
+            }
+        }
+
+        if let Some(commands) = self.doc.get("commands") {
+            collect_fields(commands, &Location::Command, &[], "", &mut fields);
+        }
+
+        for (index, profile) in profiles(&self.doc).into_iter().enumerate() {
+            let name = profile.get("name").and_then(Item::as_str).unwrap_or_default();
+            if let Some(commands) = profile.get("commands") {
+                let location = |path| Location::ProfileCommand(index, path);
+                collect_fields(commands, &location, &[], &format!("{name} / "), &mut fields);
+            }
+        }
+
+        fields
+    }
+
+    /// Write new values for fields read from an earlier version of the file.
+    /// Nothing is written if any of them changed in the file since, e.g.
+    /// because it was edited by hand meanwhile.
+    pub(crate) fn set_fields<'a>(
+        &mut self,
+        edits: impl IntoIterator<Item = (&'a Field, &'a str)>,
+    ) -> Result<(), String> {
+        let edits = edits.into_iter().collect::<Vec<_>>();
+        let current = self.fields();
+        if let Some((field, _)) = edits.iter().find(|(field, _)| !current.contains(field)) {
+            return Err(format!(
+                "{}: {} changed in the config file, reopen the settings to edit it",
+                field.label, field.key
+            ));
+        }
+
+        edits.into_iter().try_for_each(|(field, text)| self.set_field(field, text))
+    }
+
+    /// Write a new value for a field. An empty hotkey is removed, or set to
+    /// `true` for commands that need the key to be present.
+    pub(crate) fn set_field(&mut self, field: &Field, text: &str) -> Result<(), String> {
+        let text = text.trim();
+
+        let value: Option<Value> = match field.kind {
+            FieldKind::Hotkey if text.is_empty() => None,
+            FieldKind::Hotkey if is_hotkey(text) => Some(text.into()),
+            FieldKind::Hotkey => return Err(format!("{}: invalid hotkey {text:?}", field.label)),
+            FieldKind::Floats => Some(
+                parse_list::<f64>(text)
+                    .map_err(|e| format!("{}: {e}", field.label))?
+                    .into_iter()
+                    .collect::<Array>()
+                    .into(),
+            ),
+            FieldKind::Ints => Some(
+                parse_list::<i64>(text)
+                    .map_err(|e| format!("{}: {e}", field.label))?
+                    .into_iter()
+                    .collect::<Array>()
+                    .into(),
+            ),
+        };
+
+        match &field.location {
+            Location::Settings => {
+                let settings = self
+                    .doc
+                    .get_mut("settings")
+                    .and_then(Item::as_table_mut)
+                    .ok_or_else(|| "No [settings] table in config file".to_string())?;
+                match value {
+                    Some(value) => {
+                        match settings.get_mut(&field.key).and_then(Item::as_value_mut) {
+                            Some(slot) => replace(slot, value),
+                            None => {
+                                settings.insert(&field.key, Item::Value(value));
+                            },
+                        }
+                    },
+                    None if field.key == "display" => {
+                        return Err("The display hotkey can't be removed".to_string());
+                    },
+                    None => {
+                        settings.remove(&field.key);
+                    },
+                }
+            },
+            Location::Command(path) | Location::ProfileCommand(_, path) => {
+                let commands = match field.location {
+                    Location::ProfileCommand(profile, _) => profile_mut(&mut self.doc, profile)
+                        .and_then(|profile| profile.get_mut("commands")),
+                    _ => self.doc.get_mut("commands"),
+                };
+                let mut command = commands
+                    .and_then(|commands| command_mut(commands, path))
+                    .ok_or_else(|| format!("{}: command not found", field.label))?;
+                match value {
+                    Some(value) => command.set(&field.key, value),
+                    None if PLACEHOLDER_HOTKEYS.contains(&field.key.as_str()) => {
+                        command.set(&field.key, true.into())
+                    },
+                    None => command.remove(&field.key),
+                }
+            },
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+
+    const CONFIG: &str = r#"# Top comment
+commands = [
+  # No damage
+  { flag = "no_damage", hotkey = "1" },
+  { cycle_speed = [0.5, 1.0] }, # Speed
+  { group = "Warps", commands = [{ position = true }] },
+]
+
+[settings]
+display = "0" # Open the tool
+# Indicators go here
+indicators = [{ indicator = "igt", enabled = true }, { indicator = "fps" }]
+
+[[profiles]]
+name = "Bosses"
+indicators = [{ indicator = "deaths", enabled = false }]
+
+[[profiles.commands]]
+runes = 1000
+hotkey = "f1"
+"#;
+
+    fn field<'a>(fields: &'a [Field], label: &str, key: &str) -> &'a Field {
+        fields.iter().find(|f| f.label == label && f.key == key).unwrap()
+    }
+
+    #[test]
+    fn test_fields() {
+        let doc = ConfigDocument::parse(CONFIG).unwrap();
+        let fields = doc
+            .fields()
+            .into_iter()
+            .map(|f| (f.location, f.label, f.key, f.kind, f.value))
+            .collect::<Vec<_>>();
+
+        let field = |location, label: &str, key: &str, kind, value: &str| {
+            (location, label.to_string(), key.to_string(), kind, value.to_string())
+        };
+        assert_eq!(fields, [
+            field(Location::Settings, "settings", "display", FieldKind::Hotkey, "0"),
+            field(Location::Settings, "settings", "hide", FieldKind::Hotkey, ""),
+            field(Location::Settings, "settings", "cycle_profile", FieldKind::Hotkey, ""),
+            field(
+                Location::Command(vec![0]),
+                "flag = \"no_damage\"",
+                "hotkey",
+                FieldKind::Hotkey,
+                "1"
+            ),
+            field(
+                Location::Command(vec![1]),
+                "cycle_speed = [0.5, 1.0]",
+                "cycle_speed",
+                FieldKind::Floats,
+                "0.5, 1.0"
+            ),
+            field(
+                Location::Command(vec![1]),
+                "cycle_speed = [0.5, 1.0]",
+                "hotkey",
+                FieldKind::Hotkey,
+                ""
+            ),
+            field(
+                Location::Command(vec![2, 0]),
+                "Warps / position = true",
+                "position",
+                FieldKind::Hotkey,
+                ""
+            ),
+            field(
+                Location::ProfileCommand(0, vec![0]),
+                "Bosses / runes = 1000",
+                "hotkey",
+                FieldKind::Hotkey,
+                "f1"
+            ),
+        ]);
+    }
+
+    #[test]
+    fn test_set_field() {
+        let mut doc = ConfigDocument::parse(CONFIG).unwrap();
+        let fields = doc.fields();
+
+        doc.set_field(field(&fields, "settings", "display"), "f2").unwrap();
+        doc.set_field(field(&fields, "settings", "hide"), "f3").unwrap();
+        doc.set_field(field(&fields, "flag = \"no_damage\"", "hotkey"), "").unwrap();
+        doc.set_field(field(&fields, "cycle_speed = [0.5, 1.0]", "cycle_speed"), "1, 2.5").unwrap();
+        doc.set_field(field(&fields, "cycle_speed = [0.5, 1.0]", "hotkey"), "ctrl+2").unwrap();
+        doc.set_field(field(&fields, "Warps / position = true", "position"), "f4").unwrap();
+        doc.set_field(field(&fields, "Bosses / runes = 1000", "hotkey"), "f5").unwrap();
+
+        assert_eq!(
+            doc.doc.to_string(),
+            r#"# Top comment
+commands = [
+  # No damage
+  { flag = "no_damage" },
+  { cycle_speed = [1.0, 2.5], hotkey = "ctrl+2" }, # Speed
+  { group = "Warps", commands = [{ position = "f4" }] },
+]
+
+[settings]
+display = "f2" # Open the tool
+# Indicators go here
+indicators = [{ indicator = "igt", enabled = true }, { indicator = "fps" }]
+hide = "f3"
+
+[[profiles]]
+name = "Bosses"
+indicators = [{ indicator = "deaths", enabled = false }]
+
+[[profiles.commands]]
+runes = 1000
+hotkey = "f5"
+"#
+        );
+
+        let fields = doc.fields();
+        doc.set_field(field(&fields, "Warps / position = \"f4\"", "position"), "").unwrap();
+        doc.set_field(field(&fields, "Bosses / runes = 1000", "hotkey"), "").unwrap();
+        let text = doc.doc.to_string();
+        assert!(text.contains("commands = [{ position = true }]"), "{text}");
+        assert!(text.ends_with("[[profiles.commands]]\nrunes = 1000\n"), "{text}");
+
+        let display = field(&fields, "settings", "display");
+        assert_eq!(doc.set_field(display, ""), Err("The display hotkey can't be removed".into()));
+        assert_eq!(
+            doc.set_field(display, "nope"),
+            Err("settings: invalid hotkey \"nope\"".to_string())
+        );
+        let speeds = field(&fields, "cycle_speed = [1.0, 2.5]", "cycle_speed");
+        assert_eq!(
+            doc.set_field(speeds, "1, x"),
+            Err("cycle_speed = [1.0, 2.5]: Invalid value \"x\"".to_string())
+        );
+    }
+
+    #[test]
+    fn test_set_fields() {
+        let fields = ConfigDocument::parse(CONFIG).unwrap().fields();
+        let display = field(&fields, "settings", "display");
+        let speeds = field(&fields, "cycle_speed = [0.5, 1.0]", "cycle_speed");
+        let speed_hotkey = field(&fields, "cycle_speed = [0.5, 1.0]", "hotkey");
+
+        // Several fields of the same command.
+        let mut doc = ConfigDocument::parse(CONFIG).unwrap();
+        doc.set_fields([(display, "f2"), (speeds, "2"), (speed_hotkey, "f3")]).unwrap();
+        let text = doc.doc.to_string();
+        assert!(text.contains("{ cycle_speed = [2.0], hotkey = \"f3\" }"), "{text}");
+        assert!(text.contains("display = \"f2\""), "{text}");
+
+        // The file was edited since the fields were read.
+        let edited = CONFIG.replace("display = \"0\"", "display = \"9\"");
+        let mut doc = ConfigDocument::parse(&edited).unwrap();
+        assert_eq!(
+            doc.set_fields([(speeds, "2"), (display, "f2")]),
+            Err("settings: display changed in the config file, reopen the settings to edit it"
+                .to_string())
+        );
+        assert_eq!(doc.doc.to_string(), edited);
+        doc.set_fields([(speeds, "2")]).unwrap();
+
+        // A command was inserted before the edited one.
+        let edited = CONFIG.replace("  # No damage\n", "  { quitout = \"p\" },\n");
+        let mut doc = ConfigDocument::parse(&edited).unwrap();
+        assert!(doc.set_fields([(speed_hotkey, "f3")]).is_err());
+        assert_eq!(doc.doc.to_string(), edited);
+    }
+
+    #[test]
+    fn test_set_indicator_enabled() {
+        let mut doc = ConfigDocument::parse(CONFIG).unwrap();
+        assert!(doc.set_indicator_enabled(0, 0, false, &[]));
+        assert!(doc.set_indicator_enabled(0, 1, false, &[]));
+        assert!(doc.set_indicator_enabled(1, 0, true, &[]));
+        assert!(!doc.set_indicator_enabled(1, 1, true, &[]));
+
+        let text = doc.doc.to_string();
+        assert!(text.contains(
+            "indicators = [{ indicator = \"igt\", enabled = false }, { indicator = \"fps\", \
+             enabled = false }]"
+        ));
+        assert!(text.contains("indicators = [{ indicator = \"deaths\", enabled = true }]"));
+    }
+
+    #[test]
+    fn test_set_indicator_enabled_defaults() {
+        let mut doc = ConfigDocument::parse("[settings]\ndisplay = \"0\"\n").unwrap();
+        let mut indicators = Config::default().settings.indicators;
+        indicators.truncate(3);
+        indicators[2].enabled = true;
+
+        assert!(doc.set_indicator_enabled(0, 2, true, &indicators));
+        assert_eq!(
+            doc.doc.to_string(),
+            r#"[settings]
+display = "0"
+indicators = [
+  { indicator = "game_version", enabled = true },
+  { indicator = "igt", enabled = true },
+  { indicator = "position", enabled = true },
+]
+"#
+        );
+    }
+
+    #[test]
+    fn test_set_profile() {
+        let mut doc = ConfigDocument::parse("[settings]\ndisplay = \"0\" # Open\n").unwrap();
+        assert!(doc.set_profile(Some("Bosses")));
+        assert_eq!(
+            doc.doc.to_string(),
+            "[settings]\ndisplay = \"0\" # Open\nprofile = \"Bosses\"\n"
+        );
+        assert!(doc.set_profile(Some("Any%")));
+        assert_eq!(doc.doc.to_string(), "[settings]\ndisplay = \"0\" # Open\nprofile = \"Any%\"\n");
+        assert!(doc.set_profile(None));
+        assert_eq!(doc.doc.to_string(), "[settings]\ndisplay = \"0\" # Open\n");
+
+        assert!(!ConfigDocument::parse("").unwrap().set_profile(None));
+    }
+}
@@ -0,0 +1,158 @@
+//! Indicators backed by user-defined pointer chains.
+//!
+//! The expressions are parsed along with the config; here their symbols are
+//! resolved against the game's pointers.
+
+use std::fmt::{self, Display};
+
+use libeldenring::prelude::*;
+use practice_tool_config::{CustomIndicatorSpec, Term, ValueType};
+
+type Symbol = fn(&Pointers) -> usize;
+
+// Same names, in the same order, as `SYMBOL_NAMES`.
+const SYMBOLS: &[(&str, Symbol)] = &[
+    ("bullet_man", |p| p.base_addresses.bullet_man),
+    ("chr_dbg_flags", |p| p.base_addresses.chr_dbg_flags),
+    ("cs_flipper", |p| p.base_addresses.cs_flipper),
+    ("cs_lua_event_manager", |p| p.base_addresses.cs_lua_event_manager),
+    ("cs_menu_man", |p| p.base_addresses.cs_menu_man),
+    ("cs_menu_man_imp", |p| p.base_addresses.cs_menu_man_imp),
+    ("cs_net_man", |p| p.base_addresses.cs_net_man),
+    ("cs_regulation_manager", |p| p.base_addresses.cs_regulation_manager),
+    ("cs_session_manager", |p| p.base_addresses.cs_session_manager),
+    ("damage_ctrl", |p| p.base_addresses.damage_ctrl),
+    ("field_area", |p| p.base_addresses.field_area),
+    ("game_data_man", |p| p.base_addresses.game_data_man),
+    ("game_man", |p| p.base_addresses.game_man),
+    ("global_pos", |p| p.base_addresses.global_pos),
+    ("group_mask", |p| p.base_addresses.group_mask),
+    ("hit_ins", |p| p.base_addresses.hit_ins),
+    ("map_item_man", |p| p.base_addresses.map_item_man),
+    ("msg_repository", |p| p.base_addresses.msg_repository),
+    ("solo_param_repository", |p| p.base_addresses.solo_param_repository),
+    ("world_chr_man", |p| p.base_addresses.world_chr_man),
+    ("world_chr_man_dbg", |p| p.base_addresses.world_chr_man_dbg),
+    ("world_chr_man_imp", |p| p.base_addresses.world_chr_man_imp),
+    ("current_target", |p| p.base_addresses.current_target),
+    ("base_fps", |p| p.base_addresses.base_fps),
+    ("base_anim", |p| p.base_addresses.base_anim),
+    ("net_players_ins", |p| p.net_players_ins),
+    ("player_ins", |p| p.player_ins),
+    ("torrent_enemy_ins", |p| p.torrent_enemy_ins),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum CustomValue {
+    Int(i128),
+    Float(f64),
+}
+
+impl CustomValue {
+    pub(crate) fn as_f64(self) -> f64 {
+        match self {
+            CustomValue::Int(value) => value as f64,
+            CustomValue::Float(value) => value,
+        }
+    }
+}
+
+impl Display for CustomValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CustomValue::Int(value) => write!(f, "{value}"),
+            CustomValue::Float(value) => write!(f, "{value:.3}"),
+        }
+    }
+}
+
+fn decode(value_type: ValueType, bytes: [u8; 8]) -> CustomValue {
+    let [b0, b1, b2, b3, ..] = bytes;
+    match value_type {
+        ValueType::U8 => CustomValue::Int(b0 as i128),
+        ValueType::U16 => CustomValue::Int(u16::from_le_bytes([b0, b1]) as i128),
+        ValueType::U32 => CustomValue::Int(u32::from_le_bytes([b0, b1, b2, b3]) as i128),
+        ValueType::U64 => CustomValue::Int(u64::from_le_bytes(bytes) as i128),
+        ValueType::I8 => CustomValue::Int(b0 as i8 as i128),
+        ValueType::I16 => CustomValue::Int(i16::from_le_bytes([b0, b1]) as i128),
+        ValueType::I32 => CustomValue::Int(i32::from_le_bytes([b0, b1, b2, b3]) as i128),
+        ValueType::I64 => CustomValue::Int(i64::from_le_bytes(bytes) as i128),
+        ValueType::F32 => CustomValue::Float(f32::from_le_bytes([b0, b1, b2, b3]) as f64),
+        ValueType::F64 => CustomValue::Float(f64::from_le_bytes(bytes)),
+    }
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct CustomIndicator {
+    pub(crate) label: String,
+    pub(crate) min: Option<f64>,
+    pub(crate) max: Option<f64>,
+    value_type: ValueType,
+    chain: PointerChain<[u8; 8]>,
+}
+
+impl CustomIndicator {
+    pub(crate) fn new(spec: &CustomIndicatorSpec, pointers: &Pointers) -> Result<Self, String> {
+        let chain = spec
+            .terms
+            .iter()
+            .map(|term| match term {
+                Term::Literal(value) => Ok(*value),
+                Term::Symbol(name) => SYMBOLS
+                    .iter()
+                    .find(|(symbol, _)| symbol == name)
+                    .map(|(_, symbol)| symbol(pointers))
+                    .ok_or_else(|| format!("Unknown symbol {name:?} in custom indicator")),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        // Values narrower than 8 bytes are read along with the bytes after
+        // them, which are then ignored.
+        Ok(CustomIndicator {
+            label: spec.label.clone(),
+            min: spec.min,
+            max: spec.max,
+            value_type: spec.value_type,
+            chain: PointerChain::new(&chain),
+        })
+    }
+
+    pub(crate) fn read(&self) -> Option<CustomValue> {
+        self.chain.read().map(|bytes| decode(self.value_type, bytes))
+    }
+
+    /// Position of the current value between `min` and `max`, if both are set.
+    pub(crate) fn fraction(&self, value: CustomValue) -> Option<f32> {
+        let (min, max) = (self.min?, self.max?);
+        if max <= min {
+            return None;
+        }
+        Some(((value.as_f64() - min) / (max - min)).clamp(0., 1.) as f32)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use practice_tool_config::SYMBOL_NAMES;
+
+    use super::*;
+
+    #[test]
+    fn test_symbols() {
+        let names = SYMBOLS.iter().map(|(name, _)| *name).collect::<Vec<_>>();
+        assert_eq!(names, SYMBOL_NAMES);
+    }
+
+    #[test]
+    fn test_decode() {
+        let bytes = [0xfe, 0xff, 0xff, 0xff, 1, 0, 0, 0];
+        assert_eq!(decode(ValueType::U8, bytes), CustomValue::Int(0xfe));
+        assert_eq!(decode(ValueType::I8, bytes), CustomValue::Int(-2));
+        assert_eq!(decode(ValueType::U16, bytes), CustomValue::Int(0xfffe));
+        assert_eq!(decode(ValueType::I32, bytes), CustomValue::Int(-2));
+        assert_eq!(decode(ValueType::U64, bytes), CustomValue::Int(0x1_ffff_fffe));
+        let [b0, b1, b2, b3] = 1.5f32.to_le_bytes();
+        assert_eq!(decode(ValueType::F32, [b0, b1, b2, b3, 0, 0, 0, 0]), CustomValue::Float(1.5));
+        assert_eq!(decode(ValueType::F64, 0.25f64.to_le_bytes()), CustomValue::Float(0.25));
+    }
+}
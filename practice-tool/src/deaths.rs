@@ -0,0 +1,279 @@
+//! Death counter and per-boss attempt tracker.
+//!
+//! A death is counted when the player's HP drops to zero. Each death is also
+//! counted as an attempt against the last entity locked on in the current
+//! map, or against the map itself if nothing was locked on there. Lock-ons
+//! are only seen through the hook of the `target` command, so without it
+//! every attempt counts against the map. Lifetime totals are persisted next
+//! to the DLL.
+
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use hudhook::tracing::error;
+use libeldenring::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::template::Values;
+use crate::util;
+use crate::widgets::target::{locked_on_entity, EntityPointerChains};
+
+const DEATHS_FILE: &str = "jdsd_er_practice_tool_deaths.json";
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub(crate) struct Totals {
+    pub(crate) deaths: u64,
+    pub(crate) attempts: BTreeMap<String, u64>,
+}
+
+impl Totals {
+    fn from_json(s: &str) -> Result<Self, String> {
+        serde_json::from_str(s).map_err(|e| format!("{e}"))
+    }
+
+    fn to_json(&self) -> Result<String, String> {
+        serde_json::to_string_pretty(self).map_err(|e| format!("{e}"))
+    }
+
+    fn record(&mut self, key: &str) {
+        self.deaths += 1;
+        *self.attempts.entry(key.to_string()).or_default() += 1;
+    }
+
+    pub(crate) fn attempts(&self, key: &str) -> u64 {
+        self.attempts.get(key).copied().unwrap_or_default()
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct DeathCounter {
+    pub(crate) session: Totals,
+    pub(crate) lifetime: Totals,
+    path: Option<PathBuf>,
+    // Whether the player was alive last frame, if in game.
+    alive: Option<bool>,
+    // Last locked-on npc param id, along with the map it was seen in.
+    last_target: Option<(u32, u32)>,
+    current_key: Option<String>,
+}
+
+impl DeathCounter {
+    pub(crate) fn new() -> Self {
+        let path = util::get_dll_path().and_then(|path| path.parent().map(|p| p.join(DEATHS_FILE)));
+
+        let lifetime = path
+            .as_ref()
+            .filter(|path| path.exists())
+            .and_then(|path| {
+                std::fs::read_to_string(path)
+                    .map_err(|e| format!("{e}"))
+                    .and_then(|s| Totals::from_json(&s))
+                    .map_err(|e| error!("Couldn't read {path:?}: {e}"))
+                    .ok()
+            })
+            .unwrap_or_default();
+
+        DeathCounter::with_lifetime(lifetime, path)
+    }
+
+    fn with_lifetime(lifetime: Totals, path: Option<PathBuf>) -> Self {
+        DeathCounter {
+            session: Totals::default(),
+            lifetime,
+            path,
+            alive: None,
+            last_target: None,
+            current_key: None,
+        }
+    }
+
+    /// Sample the game state. Call once per frame.
+    pub(crate) fn update(&mut self, pointers: &Pointers) {
+        let npc_param_id =
+            locked_on_entity().and_then(|addr| EntityPointerChains::new(addr).npc_param_id.read());
+        self.update_key(pointers.global_position.read_map_id(), npc_param_id);
+
+        let hp = pointers.character_points.read().map(|points| points.hp);
+        if self.update_alive(hp) {
+            self.record_death();
+        }
+    }
+
+    /// Attribute deaths to the last entity locked on in the current map, or
+    /// to the map.
+    fn update_key(&mut self, map_id: Option<u32>, npc_param_id: Option<u32>) {
+        if let (Some(map_id), Some(npc_param_id)) = (map_id, npc_param_id) {
+            self.last_target = Some((map_id, npc_param_id));
+        }
+
+        self.current_key = match (map_id, self.last_target) {
+            (Some(map_id), Some((target_map_id, npc_param_id))) if map_id == target_map_id => {
+                Some(format!("npc {npc_param_id}"))
+            },
+            (Some(m), _) => {
+                let (a, b, r, s) = ((m >> 24) & 0xff, (m >> 16) & 0xff, (m >> 8) & 0xff, m & 0xff);
+                Some(format!("m{a:02x}_{b:02x}_{r:02x}_{s:02x}"))
+            },
+            (None, _) => self.current_key.take(),
+        };
+    }
+
+    /// Whether the player just died. Dying takes a frame in game alive first,
+    /// so loading into a save with no HP isn't a death.
+    fn update_alive(&mut self, hp: Option<i32>) -> bool {
+        let alive = hp.map(|hp| hp > 0);
+        let died = self.alive == Some(true) && alive == Some(false);
+        self.alive = alive;
+        died
+    }
+
+    fn record_death(&mut self) {
+        let key = self.current_key.as_deref().unwrap_or("unknown");
+        self.session.record(key);
+        self.lifetime.record(key);
+        self.save();
+    }
+
+    /// The boss or map the next death will be attributed to.
+    pub(crate) fn current_key(&self) -> Option<&str> {
+        self.current_key.as_deref()
+    }
+
+    pub(crate) fn reset_session(&mut self) {
+        self.session = Totals::default();
+    }
+
+    pub(crate) fn reset_lifetime(&mut self) {
+        self.session = Totals::default();
+        self.lifetime = Totals::default();
+        self.save();
+    }
+
+    /// Values for the exporter.
+    pub(crate) fn values(&self) -> Values {
+        let mut values = Values::new();
+        values.insert("deaths", self.session.deaths.to_string());
+        values.insert("deaths_total", self.lifetime.deaths.to_string());
+        if let Some(key) = self.current_key() {
+            values.insert("attempts", self.session.attempts(key).to_string());
+            values.insert("attempts_total", self.lifetime.attempts(key).to_string());
+            values.insert("attempts_key", key.to_string());
+        }
+        values
+    }
+
+    fn save(&self) {
+        let Some(path) = self.path.as_ref() else {
+            return;
+        };
+
+        let result = self
+            .lifetime
+            .to_json()
+            .and_then(|s| std::fs::write(path, s).map_err(|e| format!("{e}")));
+
+        if let Err(e) = result {
+            error!("Couldn't write {path:?}: {e}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LIMGRAVE: u32 = 0x3c2a2400;
+    const STORMVEIL: u32 = 0x0a000000;
+    const MARGIT: u32 = 10000000;
+
+    fn counter() -> DeathCounter {
+        DeathCounter::with_lifetime(Totals::default(), None)
+    }
+
+    #[test]
+    fn test_key() {
+        let mut counter = counter();
+
+        counter.update_key(Some(LIMGRAVE), None);
+        assert_eq!(counter.current_key(), Some("m3c_2a_24_00"));
+
+        counter.update_key(Some(STORMVEIL), Some(MARGIT));
+        assert_eq!(counter.current_key(), Some("npc 10000000"));
+        // Still against the boss after the lock-on is lost.
+        counter.update_key(Some(STORMVEIL), None);
+        assert_eq!(counter.current_key(), Some("npc 10000000"));
+        // Loading screens keep the key.
+        counter.update_key(None, None);
+        assert_eq!(counter.current_key(), Some("npc 10000000"));
+
+        // The boss doesn't follow into another map.
+        counter.update_key(Some(LIMGRAVE), None);
+        assert_eq!(counter.current_key(), Some("m3c_2a_24_00"));
+        counter.update_key(Some(STORMVEIL), None);
+        assert_eq!(counter.current_key(), Some("npc 10000000"));
+    }
+
+    #[test]
+    fn test_death() {
+        let mut counter = counter();
+
+        // Loading into the game with no HP isn't a death.
+        assert!(!counter.update_alive(Some(0)));
+        assert!(!counter.update_alive(Some(100)));
+
+        assert!(!counter.update_alive(Some(50)));
+        assert!(counter.update_alive(Some(0)));
+        // Still dead on the next frames, and through the loading screen.
+        assert!(!counter.update_alive(Some(0)));
+        assert!(!counter.update_alive(None));
+        assert!(!counter.update_alive(Some(100)));
+
+        assert!(counter.update_alive(Some(0)));
+    }
+
+    #[test]
+    fn test_totals() {
+        let mut counter = counter();
+        counter.update_key(Some(STORMVEIL), Some(MARGIT));
+        counter.record_death();
+        counter.record_death();
+        counter.update_key(Some(LIMGRAVE), None);
+        counter.record_death();
+
+        assert_eq!(counter.session.deaths, 3);
+        assert_eq!(counter.session.attempts("npc 10000000"), 2);
+        assert_eq!(counter.lifetime.attempts("m3c_2a_24_00"), 1);
+
+        let values = counter.values();
+        assert_eq!(values["deaths"], "3");
+        assert_eq!(values["attempts"], "1");
+        assert_eq!(values["attempts_key"], "m3c_2a_24_00");
+
+        counter.reset_session();
+        assert_eq!(counter.session.deaths, 0);
+        assert_eq!(counter.lifetime.deaths, 3);
+    }
+
+    #[test]
+    fn test_json() {
+        let mut totals = Totals::default();
+        totals.record("npc 10000000");
+        totals.record("npc 10000000");
+        totals.record("m3c_2a_24_00");
+
+        let json = totals.to_json().unwrap();
+        assert_eq!(
+            serde_json::from_str::<serde_json::Value>(&json).unwrap(),
+            serde_json::json!({
+                "deaths": 3,
+                "attempts": { "m3c_2a_24_00": 1, "npc 10000000": 2 },
+            })
+        );
+
+        let read = Totals::from_json(&json).unwrap();
+        assert_eq!(read.deaths, 3);
+        assert_eq!(read.attempts, totals.attempts);
+
+        assert!(Totals::from_json("{\"deaths\": 1}").is_err());
+    }
+}
@@ -0,0 +1,215 @@
+//! Periodic export of indicator values to files, e.g. for OBS text sources.
+//!
+//! Files are written next to the DLL by a background thread, and only when
+//! their contents change. A file with a template gets the rendered template;
+//! a file without one gets every value as a JSON object.
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use hudhook::tracing::error;
+use libeldenring::prelude::*;
+
+use crate::config::{ExportFile, ExportSettings};
+use crate::template::{self, Values};
+use crate::util;
+use crate::widgets::target::{locked_on_entity, EntityPointerChains};
+
+/// Read the values the in-game indicators display.
+pub(crate) fn indicator_values(pointers: &Pointers, framecount: u32, game_version: &str) -> Values {
+    let mut values = Values::new();
+
+    values.insert("game_version", game_version.to_string());
+    values.insert("frame_count", framecount.to_string());
+
+    if let Some(igt) = pointers.igt.read() {
+        let millis = (igt % 1000) / 10;
+        let total_seconds = igt / 1000;
+        let seconds = total_seconds % 60;
+        let minutes = total_seconds / 60 % 60;
+        let hours = total_seconds / 3600;
+        values.insert("igt", format!("{hours:02}:{minutes:02}:{seconds:02}.{millis:02}"));
+        values.insert("igt_ms", igt.to_string());
+        values.insert("hours", hours.to_string());
+        values.insert("minutes", minutes.to_string());
+        values.insert("seconds", seconds.to_string());
+        values.insert("millis", (igt % 1000).to_string());
+    }
+
+    if let Some([x, y, z, _a1, _a2]) = pointers.global_position.read() {
+        values.insert("x", format!("{x:.3}"));
+        values.insert("y", format!("{y:.3}"));
+        values.insert("z", format!("{z:.3}"));
+    }
+
+    if let Some(m) = pointers.global_position.read_map_id() {
+        let (a, b, r, s) = ((m >> 24) & 0xff, (m >> 16) & 0xff, (m >> 8) & 0xff, m & 0xff);
+        values.insert("map", format!("m{a:02x}_{b:02x}_{r:02x}_{s:02x}"));
+    }
+
+    if let Some(cur_anim) = pointers.cur_anim.read() {
+        values.insert("animation", cur_anim.to_string());
+    }
+
+    if let (Some(time), Some(length)) =
+        (pointers.cur_anim_time.read(), pointers.cur_anim_length.read())
+    {
+        values.insert("animation_time", format!("{time:.3}"));
+        values.insert("animation_length", format!("{length:.3}"));
+    }
+
+    if let Some(fps) = pointers.fps.read() {
+        values.insert("fps", fps.to_string());
+    }
+
+    if let Some([hp, _, max_hp]) =
+        locked_on_entity().and_then(|addr| EntityPointerChains::new(addr).hp.read())
+    {
+        values.insert("target_hp", hp.to_string());
+        values.insert("target_max_hp", max_hp.to_string());
+    }
+
+    values
+}
+
+pub(crate) struct Exporter {
+    settings: ExportSettings,
+    directory: PathBuf,
+    last_export: Option<Instant>,
+    tx: Sender<Vec<(PathBuf, String)>>,
+}
+
+impl Exporter {
+    pub(crate) fn new(settings: ExportSettings) -> Self {
+        let (tx, rx) = mpsc::channel();
+
+        thread::Builder::new()
+            .name("export".to_string())
+            .spawn(move || write_files(rx))
+            .map_err(|e| error!("Couldn't start export thread: {e}"))
+            .ok();
+
+        let directory = util::get_dll_path()
+            .and_then(|path| path.parent().map(|p| p.to_path_buf()))
+            .unwrap_or_default();
+
+        Exporter { settings, directory, last_export: None, tx }
+    }
+
+    /// Whether enough time has passed since the last export.
+    pub(crate) fn due(&self) -> bool {
+        self.last_export
+            .is_none_or(|t| t.elapsed() >= Duration::from_millis(self.settings.interval))
+    }
+
+    pub(crate) fn export(&mut self, values: &Values) {
+        self.last_export = Some(Instant::now());
+
+        let files = self
+            .settings
+            .files
+            .iter()
+            .map(|file| (self.directory.join(&file.path), contents(file, values)))
+            .collect();
+
+        self.tx.send(files).ok();
+    }
+}
+
+fn contents(file: &ExportFile, values: &Values) -> String {
+    match &file.template {
+        Some(t) => template::render(t, values),
+        None => serde_json::to_string_pretty(values).unwrap_or_default(),
+    }
+}
+
+/// What was last written to each file.
+#[derive(Default)]
+struct Written(HashMap<PathBuf, String>);
+
+impl Written {
+    /// Keep the files whose contents differ from what was last written to
+    /// them, and remember these contents as written.
+    fn changed(&mut self, files: Vec<(PathBuf, String)>) -> Vec<(PathBuf, String)> {
+        files
+            .into_iter()
+            .filter(|(path, contents)| {
+                if self.0.get(path) == Some(contents) {
+                    return false;
+                }
+                self.0.insert(path.clone(), contents.clone());
+                true
+            })
+            .collect()
+    }
+}
+
+fn write_files(rx: Receiver<Vec<(PathBuf, String)>>) {
+    let mut written = Written::default();
+
+    // Exits when the `Exporter` is dropped.
+    while let Ok(files) = rx.recv() {
+        // Failures are only reported once per distinct content.
+        for (path, contents) in written.changed(files) {
+            if let Err(e) = std::fs::write(&path, contents) {
+                error!("Couldn't export to {path:?}: {e}");
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn files(contents: &[(&str, &str)]) -> Vec<(PathBuf, String)> {
+        contents
+            .iter()
+            .map(|(path, contents)| (PathBuf::from(path), contents.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_changed() {
+        let mut written = Written::default();
+
+        let first = files(&[("igt.txt", "IGT 00:01"), ("state.json", "{}")]);
+        assert_eq!(written.changed(first.clone()), first);
+        assert_eq!(written.changed(first), []);
+
+        assert_eq!(
+            written.changed(files(&[("igt.txt", "IGT 00:02"), ("state.json", "{}")])),
+            files(&[("igt.txt", "IGT 00:02")])
+        );
+        // Going back to earlier contents is still a change.
+        assert_eq!(
+            written.changed(files(&[("igt.txt", "IGT 00:01")])),
+            files(&[("igt.txt", "IGT 00:01")])
+        );
+        assert_eq!(written.changed(files(&[("other.txt", "{}")])), files(&[("other.txt", "{}")]));
+    }
+
+    #[test]
+    fn test_contents() {
+        let mut values = Values::new();
+        values.insert("igt", "00:01:02.03".to_string());
+        values.insert("deaths", "4".to_string());
+
+        let file = |template: Option<&str>| ExportFile {
+            path: PathBuf::from("out.txt"),
+            template: template.map(str::to_string),
+        };
+
+        assert_eq!(
+            contents(&file(Some("IGT {igt}, {deaths} deaths")), &values),
+            "IGT 00:01:02.03, 4 deaths"
+        );
+        assert_eq!(
+            contents(&file(None), &values),
+            "{\n  \"deaths\": \"4\",\n  \"igt\": \"00:01:02.03\"\n}"
+        );
+    }
+}
@@ -0,0 +1,203 @@
+//! Hit counter for no-hit practice.
+//!
+//! A hit is any decrease of the player's HP while the maximum HP stays the
+//! same, which leaves out respawning, leveling up and swapping talismans.
+//! HP lost while no damage is on, and damage taken while falling fast or
+//! shortly after, which is fall damage, aren't counted. Everything else is:
+//! poison, bleed and rot ticks, and skills that cost HP count as hits too.
+//!
+//! Hits are tallied per segment and in total. Segments end on the splits made
+//! with the tool's own LiveSplit hotkey; splits made in LiveSplit itself
+//! aren't seen.
+
+use crate::template::Values;
+
+/// Falling faster than this, in units per second, ends in fall damage rather
+/// than in a hit. Running down slopes is much slower.
+const FALL_SPEED: f32 = 8.;
+/// Seconds after falling in which damage is still fall damage.
+const FALL_GRACE: f32 = 0.5;
+
+/// What the hit counter reads from the game each frame.
+#[derive(Debug, Default, Clone, Copy)]
+pub(crate) struct HitSample {
+    pub(crate) hp: i32,
+    pub(crate) max_hp: i32,
+    /// Height of the player.
+    pub(crate) y: Option<f32>,
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct HitCounter {
+    pub(crate) segment: u32,
+    pub(crate) total: u32,
+    last: Option<HitSample>,
+    // Seconds left in which damage is fall damage.
+    falling: f32,
+}
+
+impl HitCounter {
+    /// Feed the player's state and the frame duration in seconds. Call once
+    /// per frame.
+    pub(crate) fn update(&mut self, sample: Option<HitSample>, no_damage: bool, dt: f32) {
+        let last = std::mem::replace(&mut self.last, sample);
+        let (Some(last), Some(sample)) = (last, sample) else {
+            return;
+        };
+
+        if let (Some(last_y), Some(y)) = (last.y, sample.y) {
+            if dt > 0. && (y - last_y) / dt < -FALL_SPEED {
+                self.falling = FALL_GRACE;
+            } else {
+                self.falling = (self.falling - dt).max(0.);
+            }
+        }
+
+        if !no_damage && sample.max_hp == last.max_hp && sample.hp < last.hp && self.falling == 0. {
+            self.segment += 1;
+            self.total += 1;
+        }
+    }
+
+    /// Start a new segment.
+    pub(crate) fn split(&mut self) {
+        self.segment = 0;
+    }
+
+    pub(crate) fn reset(&mut self) {
+        self.segment = 0;
+        self.total = 0;
+    }
+
+    /// Values for the exporter.
+    pub(crate) fn values(&self) -> Values {
+        let mut values = Values::new();
+        values.insert("hits", self.segment.to_string());
+        values.insert("hits_total", self.total.to_string());
+        values
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DT: f32 = 1. / 60.;
+
+    struct Player {
+        counter: HitCounter,
+        sample: HitSample,
+    }
+
+    impl Player {
+        fn new() -> Self {
+            let sample = HitSample { hp: 1000, max_hp: 1000, y: Some(0.) };
+            let mut counter = HitCounter::default();
+            counter.update(Some(sample), false, DT);
+            Player { counter, sample }
+        }
+
+        /// Advance by a frame, with the player's state changed by `f`.
+        fn frame(&mut self, f: impl FnOnce(&mut HitSample)) {
+            f(&mut self.sample);
+            self.counter.update(Some(self.sample), false, DT);
+        }
+
+        fn idle(&mut self, frames: usize) {
+            (0..frames).for_each(|_| self.frame(|_| {}));
+        }
+
+        fn hits(&self) -> (u32, u32) {
+            (self.counter.segment, self.counter.total)
+        }
+    }
+
+    fn damage(sample: &mut HitSample) {
+        sample.hp -= 100;
+    }
+
+    #[test]
+    fn test_hits() {
+        let mut player = Player::new();
+
+        player.frame(damage);
+        assert_eq!(player.hits(), (1, 1));
+
+        // Damage on consecutive frames, e.g. multi-hit attacks or status ticks.
+        player.idle(30);
+        player.frame(damage);
+        player.frame(damage);
+        assert_eq!(player.hits(), (3, 3));
+
+        // Staying at the same HP isn't another hit.
+        player.idle(30);
+        assert_eq!(player.hits(), (3, 3));
+    }
+
+    #[test]
+    fn test_fall_damage() {
+        let mut player = Player::new();
+
+        for _ in 0..30 {
+            player.frame(|s| s.y = s.y.map(|y| y - 0.5));
+        }
+        player.frame(damage);
+        assert_eq!(player.hits(), (0, 0));
+
+        // Hits after landing count again.
+        player.idle(60);
+        player.frame(damage);
+        assert_eq!(player.hits(), (1, 1));
+
+        // Walking down a slope isn't falling.
+        for _ in 0..30 {
+            player.frame(|s| s.y = s.y.map(|y| y - 0.05));
+        }
+        player.frame(damage);
+        assert_eq!(player.hits(), (2, 2));
+    }
+
+    #[test]
+    fn test_ignored_changes() {
+        let mut player = Player::new();
+
+        // Swapping talismans or respawning with a different max HP.
+        player.frame(|s| {
+            s.hp -= 100;
+            s.max_hp -= 100;
+        });
+        // Healing.
+        player.frame(|s| s.hp += 50);
+        assert_eq!(player.hits(), (0, 0));
+
+        // No damage is on.
+        player.sample.hp -= 100;
+        player.counter.update(Some(player.sample), true, DT);
+        assert_eq!(player.hits(), (0, 0));
+
+        // Not in game: nothing to compare against.
+        player.counter.update(None, false, DT);
+        player.frame(damage);
+        assert_eq!(player.hits(), (0, 0));
+    }
+
+    #[test]
+    fn test_segments() {
+        let mut player = Player::new();
+        let hit = |player: &mut Player| {
+            player.frame(damage);
+            player.idle(30);
+        };
+
+        hit(&mut player);
+        hit(&mut player);
+        player.counter.split();
+        hit(&mut player);
+        assert_eq!(player.hits(), (1, 3));
+
+        player.counter.reset();
+        assert_eq!(player.hits(), (0, 0));
+        hit(&mut player);
+        assert_eq!(player.hits(), (1, 1));
+    }
+}
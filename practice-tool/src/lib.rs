@@ -14,8 +14,19 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
+mod animations;
+mod api;
 mod config;
+mod config_writer;
+mod custom_indicator;
+mod deaths;
+mod export;
+mod hits;
+mod livesplit;
 mod practice_tool;
+mod settings_editor;
+mod speedometer;
+mod template;
 mod widgets;
 
 pub mod update;
@@ -0,0 +1,305 @@
+//! Client for the LiveSplit Server component.
+//!
+//! LiveSplit Server speaks a line-based protocol over TCP. The connection is
+//! owned by a background thread so that the render loop never blocks on the
+//! socket; commands are queued through a channel and dropped while LiveSplit
+//! is unreachable.
+
+use std::fmt::{self, Display};
+use std::io::{self, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use hudhook::tracing::{debug, error, info};
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(1);
+const RECONNECT_INTERVAL: Duration = Duration::from_secs(5);
+
+// The IGT stops advancing during loading screens. Consecutive frames can read
+// the same IGT value at high framerates, so only consider the game loading
+// after the timer has been still for a little while.
+const LOADING_THRESHOLD: Duration = Duration::from_millis(100);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Command {
+    InitGameTime,
+    SetGameTime(u64),
+    PauseGameTime,
+    UnpauseGameTime,
+    Split,
+    Reset,
+}
+
+impl Display for Command {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Command::InitGameTime => write!(f, "initgametime"),
+            Command::SetGameTime(igt) => {
+                let millis = igt % 1000;
+                let seconds = igt / 1000 % 60;
+                let minutes = igt / 60_000 % 60;
+                let hours = igt / 3_600_000;
+                write!(f, "setgametime {hours}:{minutes:02}:{seconds:02}.{millis:03}")
+            },
+            Command::PauseGameTime => write!(f, "pausegametime"),
+            Command::UnpauseGameTime => write!(f, "unpausegametime"),
+            Command::Split => write!(f, "split"),
+            Command::Reset => write!(f, "reset"),
+        }
+    }
+}
+
+/// A connection to a LiveSplit Server instance.
+pub(crate) struct Client {
+    stream: TcpStream,
+}
+
+impl Client {
+    pub(crate) fn connect(address: &str) -> io::Result<Self> {
+        let addr = address.to_socket_addrs()?.next().ok_or_else(|| {
+            io::Error::new(io::ErrorKind::NotFound, format!("Couldn't resolve {address}"))
+        })?;
+        let stream = TcpStream::connect_timeout(&addr, CONNECT_TIMEOUT)?;
+        stream.set_nodelay(true)?;
+        Ok(Client { stream })
+    }
+
+    pub(crate) fn send(&mut self, command: Command) -> io::Result<()> {
+        write!(self.stream, "{command}\r\n")?;
+        self.stream.flush()
+    }
+}
+
+/// Turns successive IGT readings into the LiveSplit commands that keep the
+/// game time in sync.
+#[derive(Debug, Default)]
+pub(crate) struct TimerSync {
+    last_igt: Option<u64>,
+    last_change: Option<Instant>,
+    paused: bool,
+    auto_reset: bool,
+}
+
+impl TimerSync {
+    /// With `auto_reset`, the run is reset whenever the IGT goes backwards.
+    pub(crate) fn new(auto_reset: bool) -> Self {
+        TimerSync { auto_reset, ..Default::default() }
+    }
+
+    /// Feed the IGT read at `now`. `None` means the timer is unavailable,
+    /// e.g. in the main menu.
+    pub(crate) fn update(&mut self, igt: Option<u64>, now: Instant) -> Vec<Command> {
+        let mut commands = Vec::new();
+
+        let Some(igt) = igt else {
+            if self.last_igt.is_some() && !self.paused {
+                self.paused = true;
+                commands.push(Command::PauseGameTime);
+            }
+            return commands;
+        };
+
+        match self.last_igt {
+            Some(last_igt) if last_igt == igt => {
+                let still_for = self.last_change.map(|t| now.duration_since(t)).unwrap_or_default();
+                if !self.paused && still_for >= LOADING_THRESHOLD {
+                    self.paused = true;
+                    commands.push(Command::PauseGameTime);
+                }
+                return commands;
+            },
+            // The IGT only ever goes backwards when a different character or
+            // savefile is loaded. That's a new attempt for some runners, but
+            // just practice for most, so it's only a reset when asked for.
+            Some(last_igt) if igt < last_igt && self.auto_reset => commands.push(Command::Reset),
+            _ => {},
+        }
+
+        if self.paused {
+            self.paused = false;
+            commands.push(Command::UnpauseGameTime);
+        }
+
+        commands.push(Command::SetGameTime(igt));
+        self.last_igt = Some(igt);
+        self.last_change = Some(now);
+
+        commands
+    }
+}
+
+/// Handle to the background LiveSplit connection.
+pub(crate) struct LiveSplit {
+    tx: Sender<Command>,
+    sync: TimerSync,
+}
+
+impl LiveSplit {
+    pub(crate) fn new(address: String, auto_reset: bool) -> Self {
+        let (tx, rx) = mpsc::channel();
+
+        thread::Builder::new()
+            .name("livesplit".to_string())
+            .spawn(move || run_connection(address, rx))
+            .map_err(|e| error!("Couldn't start LiveSplit thread: {e}"))
+            .ok();
+
+        LiveSplit { tx, sync: TimerSync::new(auto_reset) }
+    }
+
+    pub(crate) fn update(&mut self, igt: Option<u64>) {
+        for command in self.sync.update(igt, Instant::now()) {
+            self.send(command);
+        }
+    }
+
+    pub(crate) fn send(&self, command: Command) {
+        self.tx.send(command).ok();
+    }
+}
+
+fn run_connection(address: String, rx: Receiver<Command>) {
+    let mut client: Option<Client> = None;
+    let mut last_attempt: Option<Instant> = None;
+    let mut paused = false;
+
+    // Exits when the `LiveSplit` handle is dropped.
+    while let Ok(command) = rx.recv() {
+        match command {
+            Command::PauseGameTime => paused = true,
+            Command::UnpauseGameTime => paused = false,
+            _ => {},
+        }
+
+        if client.is_none() && last_attempt.is_none_or(|t| t.elapsed() >= RECONNECT_INTERVAL) {
+            last_attempt = Some(Instant::now());
+            client = connect(&address, paused);
+        }
+
+        if let Some(c) = client.as_mut() {
+            if let Err(e) = c.send(command) {
+                error!("LiveSplit connection lost: {e}");
+                client = None;
+            }
+        }
+    }
+}
+
+fn connect(address: &str, paused: bool) -> Option<Client> {
+    let mut client = match Client::connect(address) {
+        Ok(client) => client,
+        Err(e) => {
+            debug!("Couldn't connect to LiveSplit at {address}: {e}");
+            return None;
+        },
+    };
+
+    let init = client.send(Command::InitGameTime).and_then(|_| {
+        if paused {
+            client.send(Command::PauseGameTime)
+        } else {
+            Ok(())
+        }
+    });
+
+    match init {
+        Ok(()) => {
+            info!("Connected to LiveSplit at {address}");
+            Some(client)
+        },
+        Err(e) => {
+            error!("Couldn't initialize LiveSplit game time: {e}");
+            None
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{BufRead, BufReader};
+    use std::net::TcpListener;
+
+    use super::*;
+
+    fn read_lines(listener: &TcpListener, count: usize) -> Vec<String> {
+        let (stream, _) = listener.accept().unwrap();
+        stream.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+        BufReader::new(stream).lines().take(count).map(Result::unwrap).collect()
+    }
+
+    #[test]
+    fn test_command_format() {
+        assert_eq!(Command::SetGameTime(0).to_string(), "setgametime 0:00:00.000");
+        assert_eq!(Command::SetGameTime(3_723_004).to_string(), "setgametime 1:02:03.004");
+        assert_eq!(Command::SetGameTime(36_000_000).to_string(), "setgametime 10:00:00.000");
+        assert_eq!(Command::PauseGameTime.to_string(), "pausegametime");
+        assert_eq!(Command::Split.to_string(), "split");
+    }
+
+    #[test]
+    fn test_client_mock_server() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+
+        let mut client = Client::connect(&address).unwrap();
+        client.send(Command::SetGameTime(1500)).unwrap();
+        client.send(Command::Split).unwrap();
+        client.send(Command::Reset).unwrap();
+
+        assert_eq!(read_lines(&listener, 3), ["setgametime 0:00:01.500", "split", "reset"]);
+    }
+
+    #[test]
+    fn test_livesplit_mock_server() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let livesplit = LiveSplit::new(listener.local_addr().unwrap().to_string(), false);
+
+        livesplit.send(Command::SetGameTime(1000));
+        livesplit.send(Command::Split);
+
+        assert_eq!(read_lines(&listener, 3), ["initgametime", "setgametime 0:00:01.000", "split"]);
+    }
+
+    #[test]
+    fn test_timer_sync() {
+        let t0 = Instant::now();
+        let at = |ms| t0 + Duration::from_millis(ms);
+        let mut sync = TimerSync::default();
+
+        assert_eq!(sync.update(None, at(0)), []);
+        assert_eq!(sync.update(Some(1000), at(0)), [Command::SetGameTime(1000)]);
+        assert_eq!(sync.update(Some(1016), at(16)), [Command::SetGameTime(1016)]);
+
+        // Same value for a single frame is not a load.
+        assert_eq!(sync.update(Some(1016), at(20)), []);
+        assert_eq!(sync.update(Some(1016), at(200)), [Command::PauseGameTime]);
+        assert_eq!(sync.update(Some(1016), at(300)), []);
+        assert_eq!(sync.update(Some(1032), at(316)), [
+            Command::UnpauseGameTime,
+            Command::SetGameTime(1032)
+        ]);
+
+        // Quitting to the main menu pauses, and loading an earlier save only
+        // rewinds the game time.
+        assert_eq!(sync.update(None, at(400)), [Command::PauseGameTime]);
+        assert_eq!(sync.update(None, at(500)), []);
+        assert_eq!(sync.update(Some(500), at(600)), [
+            Command::UnpauseGameTime,
+            Command::SetGameTime(500)
+        ]);
+    }
+
+    #[test]
+    fn test_timer_sync_auto_reset() {
+        let t0 = Instant::now();
+        let at = |ms| t0 + Duration::from_millis(ms);
+        let mut sync = TimerSync::new(true);
+
+        assert_eq!(sync.update(Some(1000), at(0)), [Command::SetGameTime(1000)]);
+        assert_eq!(sync.update(Some(2000), at(16)), [Command::SetGameTime(2000)]);
+        assert_eq!(sync.update(Some(500), at(32)), [Command::Reset, Command::SetGameTime(500)]);
+        assert_eq!(sync.update(Some(516), at(48)), [Command::SetGameTime(516)]);
+    }
+}
@@ -1,8 +1,9 @@
 use std::fmt::Write;
+use std::path::PathBuf;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Mutex;
 use std::thread;
-use std::time::{Duration, Instant};
+use std::time::{Duration, Instant, SystemTime};
 
 use const_format::formatcp;
 use hudhook::tracing::metadata::LevelFilter;
@@ -19,8 +20,20 @@ use sys::ImVec2;
 use tracing_subscriber::prelude::*;
 use windows::Win32::UI::Input::XboxController::{XINPUT_GAMEPAD_A, XINPUT_GAMEPAD_B, XINPUT_STATE};
 
-use crate::config::{Config, IndicatorType, RadialMenu, Settings};
+use crate::animations::AnimationHistory;
+use crate::api::{Api, ControlRegistry};
+use crate::config::{make_commands, Config, IndicatorType, RadialMenu, Settings, DEFAULT_PROFILE};
+use crate::config_writer::ConfigDocument;
+use crate::custom_indicator::CustomIndicator;
+use crate::deaths::DeathCounter;
+use crate::export::{self, Exporter};
+use crate::hits::{HitCounter, HitSample};
+use crate::livesplit::{self, LiveSplit};
+use crate::settings_editor::SettingsEditor;
+use crate::speedometer::{self, Speedometer};
+use crate::template::{self, Values};
 use crate::update::Update;
+use crate::widgets::frame_advance;
 use crate::{util, XINPUTGETSTATE};
 
 const MAJOR: usize = pkg_version_major!();
@@ -29,6 +42,9 @@ const PATCH: usize = pkg_version_patch!();
 
 pub(crate) static BLOCK_XINPUT: AtomicBool = AtomicBool::new(false);
 
+const CONFIG_FILE: &str = "jdsd_er_practice_tool.toml";
+const CONFIG_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
 struct FontIDs {
     small: FontId,
     normal: FontId,
@@ -45,7 +61,10 @@ enum UiState {
 }
 
 pub(crate) struct PracticeTool {
+    config: Config,
+    profile: usize,
     settings: Settings,
+    custom_indicators: Vec<Option<CustomIndicator>>,
     pointers: Pointers,
     version_label: String,
     widgets: Vec<Box<dyn Widget>>,
@@ -57,11 +76,23 @@ pub(crate) struct PracticeTool {
     ui_state: UiState,
     fonts: Option<FontIDs>,
     config_err: Option<String>,
+    config_mtime: Option<SystemTime>,
+    config_checked: Instant,
+    settings_editor: SettingsEditor,
     update_available: Update,
+    livesplit: Option<LiveSplit>,
+    api: Option<Api>,
+    control_registry: ControlRegistry,
+    exporter: Option<Exporter>,
+    deaths: DeathCounter,
+    hits: HitCounter,
+    animation_history: AnimationHistory,
+    speedometer: Speedometer,
 
     position_bufs: [String; 4],
     position_prev: [f32; 3],
     position_change_buf: String,
+    speedometer_buf: String,
 
     position_dist_ref: [f32; 3],
     position_dist_buf: String,
@@ -73,6 +104,10 @@ pub(crate) struct PracticeTool {
     framecount_buf: String,
 
     cur_anim_buf: String,
+    animation_history_buf: String,
+    deaths_buf: String,
+    hits_buf: String,
+    custom_buf: String,
 
     gamepad_state: XINPUT_STATE,
     gamepad_stick: ImVec2,
@@ -86,39 +121,17 @@ impl PracticeTool {
         hudhook::alloc_console().ok();
         log_panics::init();
 
-        fn load_config() -> Result<Config, String> {
-            let config_path = crate::util::get_dll_path()
-                .map(|mut path| {
-                    path.pop();
-                    path.push("jdsd_er_practice_tool.toml");
-                    path
-                })
-                .ok_or_else(|| "Couldn't find config file".to_string())?;
-
-            if !config_path.exists() {
-                std::fs::write(&config_path, include_str!("../../jdsd_er_practice_tool.toml"))
-                    .map_err(|e| format!("Couldn't write default config file: {e}"))?;
-            }
-
-            let config_content = std::fs::read_to_string(config_path)
-                .map_err(|e| format!("Couldn't read config file: {e}"))?;
-            println!("{config_content}");
-            Config::parse(&config_content)
-        }
-
         let (config, config_err) = match load_config() {
             Ok(config) => (config, None),
             Err(e) => (
                 Config::default(),
                 Some({
                     error!("{}", e);
-                    format!(
-                        "Configuration error, please review your jdsd_er_practice_tool.toml \
-                         file.\n\n{e}"
-                    )
+                    config_error(&e)
                 }),
             ),
         };
+        let config_mtime = config_mtime(&config.includes);
 
         let log_file = util::get_dll_path()
             .map(|mut path| {
@@ -201,27 +214,41 @@ impl PracticeTool {
             let (maj, min, patch) = version::get_version().into();
             format!("Game Ver {maj}.{min:02}.{patch}")
         };
+        let livesplit = config
+            .settings
+            .livesplit
+            .as_ref()
+            .map(|ls| LiveSplit::new(ls.address.clone(), ls.auto_reset));
+        let exporter = config.settings.export.clone().map(Exporter::new);
         let settings = config.settings.clone();
-        let radial_menu = config.radial_menu.clone();
-        let widgets = config.make_commands(&pointers);
-
+        let animation_history = AnimationHistory::new(settings.animation_history.length);
+        let speedometer =
+            Speedometer::new(settings.speedometer.smoothing, settings.speedometer.peak_hold);
+        let api = config.settings.api.as_ref().map(|api| Api::new(api.port, api.token.clone()));
         let (log_tx, log_rx) = crossbeam_channel::unbounded();
-        info!("Practice tool initialized");
+        let profile = config.active_profile();
 
-        PracticeTool {
+        let mut tool = PracticeTool {
+            config,
+            profile,
             settings,
+            custom_indicators: Vec::new(),
             pointers,
             version_label,
-            widgets,
+            widgets: Vec::new(),
             log: Vec::new(),
             log_rx,
             log_tx,
             fonts: None,
             ui_state: UiState::Closed,
             config_err,
+            config_mtime,
+            config_checked: Instant::now(),
+            settings_editor: SettingsEditor::new(config_path()),
             position_prev: Default::default(),
             position_bufs: Default::default(),
             position_change_buf: Default::default(),
+            speedometer_buf: Default::default(),
             position_dist_ref: Default::default(),
             position_dist_buf: Default::default(),
             igt_buf: Default::default(),
@@ -229,14 +256,155 @@ impl PracticeTool {
             framecount: 0,
             framecount_buf: Default::default(),
             cur_anim_buf: Default::default(),
+            animation_history_buf: Default::default(),
+            deaths_buf: Default::default(),
+            hits_buf: Default::default(),
+            custom_buf: Default::default(),
             update_available,
-            radial_menu,
+            livesplit,
+            api,
+            control_registry: ControlRegistry::default(),
+            exporter,
+            deaths: DeathCounter::new(),
+            hits: Default::default(),
+            animation_history,
+            speedometer,
+            radial_menu: Vec::new(),
             gamepad_state: Default::default(),
             gamepad_stick: Default::default(),
             radial_menu_open_time: Instant::now(),
             press_queue: Vec::new(),
             release_queue: Vec::new(),
+        };
+        tool.load_profile(profile);
+
+        info!("Practice tool initialized");
+
+        tool
+    }
+
+    /// Reload the config file if it or a file it includes changed since they
+    /// were last read. Widgets, radial menu and indicators are rebuilt, and
+    /// so are the services whose settings changed; if the new files don't
+    /// parse, the previous config stays in place and the error is shown
+    /// instead.
+    fn reload_config(&mut self) {
+        if self.config_checked.elapsed() < CONFIG_POLL_INTERVAL {
+            return;
         }
+        self.config_checked = Instant::now();
+
+        let mtime = config_mtime(&self.config.includes);
+        if mtime.is_none() || mtime == self.config_mtime {
+            return;
+        }
+        self.config_mtime = mtime;
+
+        let config = match load_config() {
+            Ok(config) => config,
+            Err(e) => {
+                error!("{}", e);
+                self.config_err = Some(config_error(&e));
+                self.log_tx.send("Config error, open the tool for details".to_string()).ok();
+                return;
+            },
+        };
+
+        // Stay on the profile switched to by hotkey, unless the file now
+        // starts with a different one.
+        let current = self.config.profile_names()[self.profile];
+        let profile = if config.settings.profile == self.config.settings.profile {
+            config.profile_names().iter().position(|&name| name == current)
+        } else {
+            None
+        };
+        let profile = profile.unwrap_or_else(|| config.active_profile());
+        let settings = std::mem::replace(&mut self.config, config).settings;
+        self.config_mtime = config_mtime(&self.config.includes);
+        self.reload_services(&settings);
+        self.load_profile(profile);
+        self.config_err = None;
+
+        info!("Config reloaded");
+        self.log_tx.send("Config reloaded".to_string()).ok();
+    }
+
+    /// Restart the parts of the tool whose settings changed from `old`. The
+    /// control API keeps listening on the port it started with until the
+    /// game is restarted.
+    fn reload_services(&mut self, old: &Settings) {
+        let settings = &self.config.settings;
+
+        let livesplit = |settings: &Settings| {
+            settings.livesplit.as_ref().map(|ls| (ls.address.clone(), ls.auto_reset))
+        };
+        if livesplit(settings) != livesplit(old) {
+            self.livesplit = livesplit(settings)
+                .map(|(address, auto_reset)| LiveSplit::new(address, auto_reset));
+        }
+
+        if settings.export != old.export {
+            self.exporter = settings.export.clone().map(Exporter::new);
+        }
+
+        if settings.animation_history.length != old.animation_history.length {
+            self.animation_history = AnimationHistory::new(settings.animation_history.length);
+        }
+
+        let speedometer = &settings.speedometer;
+        if (speedometer.smoothing, speedometer.peak_hold)
+            != (old.speedometer.smoothing, old.speedometer.peak_hold)
+        {
+            self.speedometer = Speedometer::new(speedometer.smoothing, speedometer.peak_hold);
+        }
+
+        if settings.api != old.api {
+            warn!("Control API settings changed, restart the game to apply them");
+            self.log_tx.send("Restart the game to apply the control API settings".to_string()).ok();
+        }
+    }
+
+    /// Build the widgets, radial menu and indicators of the profile at
+    /// `index` in the config's profile names.
+    fn load_profile(&mut self, index: usize) {
+        let config = self.config.with_profile(index);
+        report_hotkey_conflicts(&config, &self.log_tx);
+
+        // Drop the old widgets first so they can undo their code patches
+        // before the new ones apply them again.
+        self.widgets.clear();
+        let mut control_registry = ControlRegistry::default();
+        self.settings = config.settings.clone();
+        self.custom_indicators = resolve_custom_indicators(&self.settings, &self.pointers);
+        self.radial_menu = config.radial_menu.clone();
+        self.widgets = make_commands(config, &self.pointers, &mut control_registry);
+        self.control_registry = control_registry;
+        self.profile = index;
+    }
+
+    /// Switch to the profile at `index`. With `persist`, also save it as the
+    /// one the tool starts with.
+    fn select_profile(&mut self, index: usize, persist: bool) {
+        self.load_profile(index);
+
+        let name = index.checked_sub(1).map(|i| self.config.profiles[i].name.clone());
+        let label = name.as_deref().unwrap_or(DEFAULT_PROFILE);
+        info!("Profile {label} selected");
+        self.log_tx.send(format!("Profile: {label}")).ok();
+
+        if !persist {
+            return;
+        }
+
+        // Save the change without triggering a reload.
+        match edit_config(|doc| doc.set_profile(name.as_deref())) {
+            Ok(()) => self.config_mtime = config_mtime(&self.config.includes),
+            Err(e) => {
+                error!("{}", e);
+                self.log_tx.send(e).ok();
+            },
+        }
+        self.config.settings.profile = name;
     }
 
     fn render_visible(&mut self, ui: &imgui::Ui) {
@@ -288,6 +456,14 @@ impl PracticeTool {
     fn render_closed(&mut self, ui: &imgui::Ui) {
         let [w, h] = ui.io().display_size;
 
+        // Read once per frame, for every formatted indicator.
+        let values = self
+            .settings
+            .indicators
+            .iter()
+            .any(|indicator| indicator.enabled && indicator.format.is_some())
+            .then(|| self.values());
+
         let stack_tokens = vec![
             ui.push_style_var(StyleVar::WindowRounding(0.)),
             ui.push_style_var(StyleVar::FrameBorderSize(0.)),
@@ -334,23 +510,48 @@ impl PracticeTool {
                         );
                         ui.separator();
 
-                        for indicator in &mut self.settings.indicators {
+                        for (i, indicator) in self.settings.indicators.iter_mut().enumerate() {
                             let label = match indicator.indicator {
                                 IndicatorType::GameVersion => "Game Version",
                                 IndicatorType::Position => "Player Position",
                                 IndicatorType::PositionChange => "Player Velocity",
                                 IndicatorType::PositionDistance => "Player Distance",
+                                IndicatorType::Speedometer => "Speedometer",
                                 IndicatorType::Animation => "Animation",
+                                IndicatorType::AnimationHistory => "Animation History",
                                 IndicatorType::Igt => "IGT Timer",
                                 IndicatorType::Fps => "FPS",
                                 IndicatorType::FrameCount => "Frame Counter",
                                 IndicatorType::ImguiDebug => "ImGui Debug Info",
+                                IndicatorType::Deaths => "Deaths",
+                                IndicatorType::HitCount => "Hit Counter",
+                                IndicatorType::Custom(ref custom) => custom.label.as_str(),
                             };
 
                             let mut state = indicator.enabled;
 
                             if ui.checkbox(label, &mut state) {
                                 indicator.enabled = state;
+                                if let Some(saved) =
+                                    self.config.indicators_mut(self.profile).get_mut(i)
+                                {
+                                    saved.enabled = state;
+                                }
+
+                                // Save the change without triggering a reload.
+                                let profile = self.profile;
+                                let indicators = self.config.indicators_mut(profile).clone();
+                                match edit_config(|doc| {
+                                    doc.set_indicator_enabled(profile, i, state, &indicators)
+                                }) {
+                                    Ok(()) => {
+                                        self.config_mtime = config_mtime(&self.config.includes)
+                                    },
+                                    Err(e) => {
+                                        error!("{}", e);
+                                        self.log_tx.send(e).ok();
+                                    },
+                                }
                             }
 
                             if let IndicatorType::FrameCount = indicator.indicator {
@@ -370,6 +571,53 @@ impl PracticeTool {
                                 }
                             }
 
+                            if let IndicatorType::HitCount = indicator.indicator {
+                                ui.same_line();
+
+                                let btn_reset_label = "Reset##hits";
+                                let btn_reset_width = ui.calc_text_size("Reset")[0]
+                                    + style.frame_padding[0] * 2.0;
+
+                                ui.set_cursor_pos([
+                                    ui.content_region_max()[0] - btn_reset_width,
+                                    ui.cursor_pos()[1],
+                                ]);
+
+                                if ui.button(btn_reset_label) {
+                                    self.hits.reset();
+                                }
+                            }
+
+                            if let IndicatorType::Deaths = indicator.indicator {
+                                ui.same_line();
+
+                                let btn_reset_width = ui.calc_text_size("Reset")[0]
+                                    + ui.calc_text_size("Reset all")[0]
+                                    + style.frame_padding[0] * 4.0
+                                    + style.item_spacing[0];
+
+                                ui.set_cursor_pos([
+                                    ui.content_region_max()[0] - btn_reset_width,
+                                    ui.cursor_pos()[1],
+                                ]);
+
+                                if ui.button("Reset##deaths") {
+                                    self.deaths.reset_session();
+                                }
+                                if ui.is_item_hovered() {
+                                    ui.tooltip_text("Reset this session's deaths and attempts");
+                                }
+
+                                ui.same_line();
+
+                                if ui.button("Reset all") {
+                                    self.deaths.reset_lifetime();
+                                }
+                                if ui.is_item_hovered() {
+                                    ui.tooltip_text("Also reset the lifetime totals saved to disk");
+                                }
+                            }
+
                             if let IndicatorType::PositionDistance = indicator.indicator {
                                 ui.same_line();
 
@@ -403,6 +651,53 @@ impl PracticeTool {
                         }
                     });
 
+                if !self.config.profiles.is_empty() {
+                    ui.same_line();
+
+                    if ui.small_button("Profile") {
+                        ui.open_popup("##profiles_window");
+                    }
+                }
+
+                ui.modal_popup_config("##profiles_window")
+                    .resizable(false)
+                    .movable(false)
+                    .title_bar(false)
+                    .build(|| {
+                        self.pointers.cursor_show.set(true);
+
+                        ui.text(
+                            "Each profile has its own commands,\nradial menu and indicators, as \
+                             set\nin your config file.",
+                        );
+                        ui.separator();
+
+                        let mut selected = None;
+                        for (i, name) in self.config.profile_names().into_iter().enumerate() {
+                            if ui.selectable_config(name).selected(i == self.profile).build() {
+                                selected = Some(i);
+                            }
+                        }
+                        if let Some(i) = selected.filter(|&i| i != self.profile) {
+                            self.select_profile(i, true);
+                        }
+
+                        ui.separator();
+
+                        if ui.button("Close") {
+                            ui.close_current_popup();
+                            self.pointers.cursor_show.set(false);
+                        }
+                    });
+
+                ui.same_line();
+
+                if ui.small_button("Settings") {
+                    self.settings_editor.open(ui);
+                }
+
+                self.settings_editor.render(ui, &self.pointers.cursor_show);
+
                 ui.same_line();
 
                 if ui.small_button("Help") {
@@ -512,143 +807,10 @@ impl PracticeTool {
 
                 ui.new_line();
 
-                for indicator in &self.settings.indicators {
-                    if !indicator.enabled {
-                        continue;
-                    }
-
-                    match indicator.indicator {
-                        IndicatorType::GameVersion => {
-                            ui.text(&self.version_label);
-                        },
-                        IndicatorType::Position => {
-                            if let (Some([x, y, z, _a1, _a2]), Some(m)) = (
-                                self.pointers.global_position.read(),
-                                self.pointers.global_position.read_map_id(),
-                            ) {
-                                let (a, b, r, s) =
-                                    ((m >> 24) & 0xff, (m >> 16) & 0xff, (m >> 8) & 0xff, m & 0xff);
-                                self.position_bufs.iter_mut().for_each(String::clear);
-                                write!(self.position_bufs[0], "m{a:02x}_{b:02x}_{r:02x}_{s:02x}")
-                                    .ok();
-                                write!(self.position_bufs[1], "{x:.3}").ok();
-                                write!(self.position_bufs[2], "{y:.3}").ok();
-                                write!(self.position_bufs[3], "{z:.3}").ok();
-
-                                ui.text(&self.position_bufs[0]);
-                                ui.same_line();
-                                ui.text_colored(
-                                    [0.7048, 0.1228, 0.1734, 1.],
-                                    &self.position_bufs[1],
-                                );
-                                ui.same_line();
-                                ui.text_colored(
-                                    [0.1161, 0.5327, 0.3512, 1.],
-                                    &self.position_bufs[2],
-                                );
-                                ui.same_line();
-                                ui.text_colored(
-                                    [0.1445, 0.2852, 0.5703, 1.],
-                                    &self.position_bufs[3],
-                                );
-                            }
-                        },
-                        IndicatorType::PositionChange => {
-                            if let Some([x, y, z, _a1, _a2]) = self.pointers.global_position.read()
-                            {
-                                let position_change_xyz = ((x - self.position_prev[0]).powf(2.0)
-                                    + (y - self.position_prev[1]).powf(2.0)
-                                    + (z - self.position_prev[2]).powf(2.0))
-                                .sqrt();
-
-                                let position_change_xz = ((x - self.position_prev[0]).powf(2.0)
-                                    + (z - self.position_prev[2]).powf(2.0))
-                                .sqrt();
-
-                                let position_change_y = y - self.position_prev[1];
-
-                                self.position_change_buf.clear();
-                                write!(
-                                    self.position_change_buf,
-                                    "Velocity: [XYZ] {position_change_xyz:.3} | [XZ] \
-                                     {position_change_xz:.3} | [Y] {position_change_y:.3}"
-                                )
-                                .ok();
-                                ui.text(&self.position_change_buf);
-
-                                self.position_prev = [x, y, z];
-                            }
-                        },
-                        IndicatorType::PositionDistance => {
-                            if let Some([x, y, z, _a1, _a2]) = self.pointers.global_position.read()
-                            {
-                                let position_dist_xyz = ((x - self.position_dist_ref[0]).powf(2.0)
-                                    + (y - self.position_dist_ref[1]).powf(2.0)
-                                    + (z - self.position_dist_ref[2]).powf(2.0))
-                                .sqrt();
-
-                                let position_dist_xz = ((x - self.position_dist_ref[0]).powf(2.0)
-                                    + (z - self.position_dist_ref[2]).powf(2.0))
-                                .sqrt();
-
-                                let position_dist_y = y - self.position_dist_ref[1];
-
-                                self.position_dist_buf.clear();
-                                write!(
-                                    self.position_dist_buf,
-                                    "Distance: [XYZ] {position_dist_xyz:.4} | [XZ] \
-                                     {position_dist_xz:.4} | [Y] {position_dist_y:.4}"
-                                )
-                                .ok();
-                                ui.text(&self.position_dist_buf);
-                            }
-                        },
-                        IndicatorType::Animation => {
-                            if let (Some(cur_anim), Some(cur_anim_time), Some(cur_anim_length)) = (
-                                self.pointers.cur_anim.read(),
-                                self.pointers.cur_anim_time.read(),
-                                self.pointers.cur_anim_length.read(),
-                            ) {
-                                self.cur_anim_buf.clear();
-                                write!(
-                                    self.cur_anim_buf,
-                                    "Animation {cur_anim} ({cur_anim_time}s /  {cur_anim_length}s)",
-                                )
-                                .ok();
-                                ui.text(&self.cur_anim_buf);
-                            }
-                        },
-                        IndicatorType::Igt => {
-                            if let Some(igt) = self.pointers.igt.read() {
-                                let millis = (igt % 1000) / 10;
-                                let total_seconds = igt / 1000;
-                                let seconds = total_seconds % 60;
-                                let minutes = total_seconds / 60 % 60;
-                                let hours = total_seconds / 3600;
-                                self.igt_buf.clear();
-                                write!(
-                                    self.igt_buf,
-                                    "IGT {hours:02}:{minutes:02}:{seconds:02}.{millis:02}",
-                                )
-                                .ok();
-                                ui.text(&self.igt_buf);
-                            }
-                        },
-                        IndicatorType::Fps => {
-                            if let Some(fps) = self.pointers.fps.read() {
-                                self.fps_buf.clear();
-                                write!(self.fps_buf, "FPS {fps}",).ok();
-                                ui.text(&self.fps_buf);
-                            }
-                        },
-                        IndicatorType::FrameCount => {
-                            self.framecount_buf.clear();
-                            write!(self.framecount_buf, "Frame count {0}", self.framecount,).ok();
-                            ui.text(&self.framecount_buf);
-                        },
-                        IndicatorType::ImguiDebug => {
-                            imgui_debug(ui);
-                        },
+                for i in 0..self.settings.indicators.len() {
+                    let indicator = &self.settings.indicators[i];
+                    if indicator.enabled && indicator.position.is_none() {
+                        self.render_indicator(ui, i, values.as_ref());
                     }
                 }
 
@@ -661,11 +823,287 @@ impl PracticeTool {
                 }
             });
 
+        for i in 0..self.settings.indicators.len() {
+            let indicator = &self.settings.indicators[i];
+            let Some([x, y]) = indicator.position.filter(|_| indicator.enabled) else {
+                continue;
+            };
+
+            ui.window(format!("##indicator_window_{i}"))
+                .position([w * x / 1920., h * y / 1080.], Condition::Always)
+                .position_pivot(indicator.anchor.pivot())
+                .bg_alpha(0.0)
+                .flags({
+                    WindowFlags::NO_TITLE_BAR
+                        | WindowFlags::NO_RESIZE
+                        | WindowFlags::NO_MOVE
+                        | WindowFlags::NO_SCROLLBAR
+                        | WindowFlags::NO_INPUTS
+                        | WindowFlags::ALWAYS_AUTO_RESIZE
+                })
+                .build(|| self.render_indicator(ui, i, values.as_ref()));
+        }
+
         for st in stack_tokens.into_iter().rev() {
             st.pop();
         }
     }
 
+    fn render_indicator(&mut self, ui: &imgui::Ui, index: usize, values: Option<&Values>) {
+        let indicator = &self.settings.indicators[index];
+
+        let _color = indicator.color.map(|color| ui.push_style_color(StyleColor::Text, color));
+        if let Some(font_size) = indicator.font_size {
+            ui.set_window_font_scale(font_size / ui.current_font_size());
+        }
+
+        if let Some(format) = indicator.format.as_ref() {
+            ui.text(template::render(format, values.unwrap_or(&Values::new())));
+
+            // The next position change is measured from this frame either way.
+            if matches!(indicator.indicator, IndicatorType::PositionChange) {
+                if let Some([x, y, z, _a1, _a2]) = self.pointers.global_position.read() {
+                    self.position_prev = [x, y, z];
+                }
+            }
+        } else {
+            match indicator.indicator {
+                IndicatorType::GameVersion => {
+                    ui.text(&self.version_label);
+                },
+                IndicatorType::Position => {
+                    if let (Some([x, y, z, _a1, _a2]), Some(m)) = (
+                        self.pointers.global_position.read(),
+                        self.pointers.global_position.read_map_id(),
+                    ) {
+                        let (a, b, r, s) =
+                            ((m >> 24) & 0xff, (m >> 16) & 0xff, (m >> 8) & 0xff, m & 0xff);
+                        self.position_bufs.iter_mut().for_each(String::clear);
+                        write!(self.position_bufs[0], "m{a:02x}_{b:02x}_{r:02x}_{s:02x}")
+                            .ok();
+                        write!(self.position_bufs[1], "{x:.3}").ok();
+                        write!(self.position_bufs[2], "{y:.3}").ok();
+                        write!(self.position_bufs[3], "{z:.3}").ok();
+
+                        ui.text(&self.position_bufs[0]);
+                        ui.same_line();
+                        ui.text_colored(
+                            [0.7048, 0.1228, 0.1734, 1.],
+                            &self.position_bufs[1],
+                        );
+                        ui.same_line();
+                        ui.text_colored(
+                            [0.1161, 0.5327, 0.3512, 1.],
+                            &self.position_bufs[2],
+                        );
+                        ui.same_line();
+                        ui.text_colored(
+                            [0.1445, 0.2852, 0.5703, 1.],
+                            &self.position_bufs[3],
+                        );
+                    }
+                },
+                IndicatorType::PositionChange => {
+                    if let Some([x, y, z, _a1, _a2]) = self.pointers.global_position.read()
+                    {
+                        let position_change_xyz = ((x - self.position_prev[0]).powf(2.0)
+                            + (y - self.position_prev[1]).powf(2.0)
+                            + (z - self.position_prev[2]).powf(2.0))
+                        .sqrt();
+
+                        let position_change_xz = ((x - self.position_prev[0]).powf(2.0)
+                            + (z - self.position_prev[2]).powf(2.0))
+                        .sqrt();
+
+                        let position_change_y = y - self.position_prev[1];
+
+                        self.position_change_buf.clear();
+                        write!(
+                            self.position_change_buf,
+                            "Velocity: [XYZ] {position_change_xyz:.3} | [XZ] \
+                             {position_change_xz:.3} | [Y] {position_change_y:.3}"
+                        )
+                        .ok();
+                        ui.text(&self.position_change_buf);
+
+                        self.position_prev = [x, y, z];
+                    }
+                },
+                IndicatorType::PositionDistance => {
+                    if let Some([x, y, z, _a1, _a2]) = self.pointers.global_position.read()
+                    {
+                        let position_dist_xyz = ((x - self.position_dist_ref[0]).powf(2.0)
+                            + (y - self.position_dist_ref[1]).powf(2.0)
+                            + (z - self.position_dist_ref[2]).powf(2.0))
+                        .sqrt();
+
+                        let position_dist_xz = ((x - self.position_dist_ref[0]).powf(2.0)
+                            + (z - self.position_dist_ref[2]).powf(2.0))
+                        .sqrt();
+
+                        let position_dist_y = y - self.position_dist_ref[1];
+
+                        self.position_dist_buf.clear();
+                        write!(
+                            self.position_dist_buf,
+                            "Distance: [XYZ] {position_dist_xyz:.4} | [XZ] \
+                             {position_dist_xz:.4} | [Y] {position_dist_y:.4}"
+                        )
+                        .ok();
+                        ui.text(&self.position_dist_buf);
+                    }
+                },
+                IndicatorType::Speedometer => {
+                    let speedometer = &mut self.speedometer;
+                    self.speedometer_buf.clear();
+                    write!(
+                        self.speedometer_buf,
+                        "Speed: [XZ] {:.2}/s | [Y] {:.2}/s | Peak {:.2}/s",
+                        speedometer.horizontal(),
+                        speedometer.vertical(),
+                        speedometer.peak(),
+                    )
+                    .ok();
+                    ui.text(&self.speedometer_buf);
+                    let history = speedometer.history();
+                    let scale_max = history.iter().copied().fold(1., f32::max);
+                    ui.plot_lines("##speedometer", history)
+                        .scale_min(0.)
+                        .scale_max(scale_max)
+                        .graph_size([200., 32.])
+                        .build();
+                },
+                IndicatorType::Animation => {
+                    if let (Some(cur_anim), Some(cur_anim_time), Some(cur_anim_length)) = (
+                        self.pointers.cur_anim.read(),
+                        self.pointers.cur_anim_time.read(),
+                        self.pointers.cur_anim_length.read(),
+                    ) {
+                        self.cur_anim_buf.clear();
+                        write!(
+                            self.cur_anim_buf,
+                            "Animation {cur_anim} ({cur_anim_time}s /  {cur_anim_length}s)",
+                        )
+                        .ok();
+                        ui.text(&self.cur_anim_buf);
+                    }
+                },
+                IndicatorType::AnimationHistory => {
+                    for entry in self.animation_history.entries() {
+                        let start = entry.start;
+                        self.animation_history_buf.clear();
+                        write!(
+                            self.animation_history_buf,
+                            "{:02}:{:02}.{:03} {:>5}ms {}",
+                            start / 60_000,
+                            start / 1000 % 60,
+                            start % 1000,
+                            entry.duration,
+                            entry.anim,
+                        )
+                        .ok();
+                        ui.text(&self.animation_history_buf);
+                    }
+                },
+                IndicatorType::Igt => {
+                    if let Some(igt) = self.pointers.igt.read() {
+                        let millis = (igt % 1000) / 10;
+                        let total_seconds = igt / 1000;
+                        let seconds = total_seconds % 60;
+                        let minutes = total_seconds / 60 % 60;
+                        let hours = total_seconds / 3600;
+                        self.igt_buf.clear();
+                        write!(
+                            self.igt_buf,
+                            "IGT {hours:02}:{minutes:02}:{seconds:02}.{millis:02}",
+                        )
+                        .ok();
+                        ui.text(&self.igt_buf);
+                    }
+                },
+                IndicatorType::Fps => {
+                    if let Some(fps) = self.pointers.fps.read() {
+                        self.fps_buf.clear();
+                        write!(self.fps_buf, "FPS {fps}",).ok();
+                        ui.text(&self.fps_buf);
+                    }
+                },
+                IndicatorType::FrameCount => {
+                    self.framecount_buf.clear();
+                    write!(self.framecount_buf, "Frame count {0}", self.framecount,).ok();
+                    if frame_advance::is_paused() {
+                        write!(self.framecount_buf, " (paused)").ok();
+                    }
+                    ui.text(&self.framecount_buf);
+                },
+                IndicatorType::Deaths => {
+                    let deaths = &self.deaths;
+                    self.deaths_buf.clear();
+                    write!(
+                        self.deaths_buf,
+                        "Deaths {} ({} total)",
+                        deaths.session.deaths, deaths.lifetime.deaths
+                    )
+                    .ok();
+                    if let Some(key) = deaths.current_key() {
+                        write!(
+                            self.deaths_buf,
+                            " | {key}: {} ({} total)",
+                            deaths.session.attempts(key),
+                            deaths.lifetime.attempts(key)
+                        )
+                        .ok();
+                    }
+                    ui.text(&self.deaths_buf);
+                },
+                IndicatorType::HitCount => {
+                    self.hits_buf.clear();
+                    write!(
+                        self.hits_buf,
+                        "Hits {} | Total {}",
+                        self.hits.segment, self.hits.total
+                    )
+                    .ok();
+                    ui.text(&self.hits_buf);
+                },
+                IndicatorType::ImguiDebug => {
+                    imgui_debug(ui);
+                },
+                IndicatorType::Custom(_) => {
+                    if let Some(Some(custom)) = self.custom_indicators.get(index) {
+                        if let Some(value) = custom.read() {
+                            self.custom_buf.clear();
+                            write!(self.custom_buf, "{} {value}", custom.label).ok();
+                            ui.text(&self.custom_buf);
+                            if let Some(fraction) = custom.fraction(value) {
+                                ProgressBar::new(fraction)
+                                    .size([200., 4.])
+                                    .overlay_text("")
+                                    .build(ui);
+                            }
+                        }
+                    }
+                },
+            }
+        }
+
+        if indicator.font_size.is_some() {
+            ui.set_window_font_scale(1.);
+        }
+    }
+
+    /// Every value that formatted indicators and the exporter can refer to.
+    fn values(&self) -> Values {
+        let mut values =
+            export::indicator_values(&self.pointers, self.framecount, &self.version_label);
+        values.extend(self.deaths.values());
+        values.extend(self.hits.values());
+        values.insert("speed_xz", format!("{:.3}", self.speedometer.horizontal()));
+        values.insert("speed_y", format!("{:.3}", self.speedometer.vertical()));
+        values.insert("speed_peak", format!("{:.3}", self.speedometer.peak()));
+        values
+    }
+
     fn render_hidden(&mut self, ui: &imgui::Ui) {
         for w in self.widgets.iter_mut() {
             w.interact(ui);
@@ -733,6 +1171,34 @@ impl PracticeTool {
         }
     }
 
+    fn update_livesplit(&mut self, ui: &imgui::Ui) {
+        let (Some(livesplit), Some(settings)) =
+            (self.livesplit.as_mut(), self.settings.livesplit.as_ref())
+        else {
+            return;
+        };
+
+        livesplit.update(self.pointers.igt.read().map(|igt| igt as u64));
+
+        if ui.io().want_capture_keyboard {
+            return;
+        }
+
+        if settings.split.map(|k| k.is_pressed(ui)).unwrap_or(false) {
+            livesplit.send(livesplit::Command::Split);
+            if self.settings.hit_count.split_linked {
+                self.hits.split();
+            }
+        }
+
+        if settings.reset.map(|k| k.is_pressed(ui)).unwrap_or(false) {
+            livesplit.send(livesplit::Command::Reset);
+            if self.settings.hit_count.split_linked {
+                self.hits.reset();
+            }
+        }
+    }
+
     fn render_logs(&mut self, ui: &imgui::Ui) {
         let io = ui.io();
 
@@ -809,8 +1275,45 @@ impl ImguiRenderLoop for PracticeTool {
 
         let display = self.settings.display.is_pressed(ui);
         let hide = self.settings.hide.map(|k| k.is_pressed(ui)).unwrap_or(false);
+        let cycle_profile = self.settings.cycle_profile.map(|k| k.is_pressed(ui)).unwrap_or(false);
+
+        if !frame_advance::is_paused() {
+            self.framecount += 1;
+        }
+
+        self.update_livesplit(ui);
+        self.reload_config();
+
+        if let Some(api) = self.api.as_ref() {
+            api.process(&self.control_registry, &self.pointers);
+        }
+
+        let dt = if frame_advance::is_paused() {
+            0.
+        } else {
+            speedometer::frame_time(self.pointers.fps.read(), ui.io().delta_time)
+        };
+        let position = self.pointers.global_position.read().map(|[x, y, z, _, _]| [x, y, z]);
+
+        self.deaths.update(&self.pointers);
+        self.hits.update(
+            self.pointers.character_points.read().map(|points| HitSample {
+                hp: points.hp,
+                max_hp: points.max_hp,
+                y: position.map(|[_, y, _]| y),
+            }),
+            self.pointers.no_damage.get().unwrap_or(false),
+            dt,
+        );
+        self.animation_history.update(self.pointers.cur_anim.read(), self.pointers.igt.read());
+        self.speedometer.update(position, dt);
 
-        self.framecount += 1;
+        if self.exporter.as_ref().is_some_and(Exporter::due) {
+            let values = self.values();
+            if let Some(exporter) = self.exporter.as_mut() {
+                exporter.export(&values);
+            }
+        }
 
         if !ui.io().want_capture_keyboard && (display || hide) {
             self.ui_state = match (&self.ui_state, hide) {
@@ -827,6 +1330,10 @@ impl ImguiRenderLoop for PracticeTool {
             }
         }
 
+        if !ui.io().want_capture_keyboard && cycle_profile && !self.config.profiles.is_empty() {
+            self.select_profile((self.profile + 1) % (self.config.profiles.len() + 1), false);
+        }
+
         self.render_radial(ui);
 
         match &self.ui_state {
@@ -876,6 +1383,73 @@ impl ImguiRenderLoop for PracticeTool {
     }
 }
 
+fn config_path() -> Option<PathBuf> {
+    util::get_dll_path().map(|mut path| {
+        path.pop();
+        path.push(CONFIG_FILE);
+        path
+    })
+}
+
+/// Latest modification time of the config file and the files it includes.
+fn config_mtime(includes: &[PathBuf]) -> Option<SystemTime> {
+    let config_path = config_path()?;
+    std::iter::once(&config_path)
+        .chain(includes)
+        .filter_map(|path| std::fs::metadata(path).and_then(|metadata| metadata.modified()).ok())
+        .max()
+}
+
+fn load_config() -> Result<Config, String> {
+    let config_path = config_path().ok_or_else(|| "Couldn't find config file".to_string())?;
+
+    if !config_path.exists() {
+        std::fs::write(&config_path, include_str!("../../jdsd_er_practice_tool.toml"))
+            .map_err(|e| format!("Couldn't write default config file: {e}"))?;
+    }
+
+    Config::load(&config_path)
+}
+
+/// Apply an edit to the config file, keeping its formatting and comments.
+fn edit_config(edit: impl FnOnce(&mut ConfigDocument) -> bool) -> Result<(), String> {
+    let config_path = config_path().ok_or_else(|| "Couldn't find config file".to_string())?;
+    let mut doc = ConfigDocument::load(&config_path)?;
+    if !edit(&mut doc) {
+        return Err("Couldn't find the setting in the config file".to_string());
+    }
+    doc.save(&config_path)
+}
+
+fn config_error(e: &str) -> String {
+    format!("Configuration error, please review your jdsd_er_practice_tool.toml file.\n\n{e}")
+}
+
+fn report_hotkey_conflicts(config: &Config, log_tx: &Sender<String>) {
+    for conflict in config.hotkey_conflicts() {
+        warn!("{}", conflict);
+        log_tx.send(conflict).ok();
+    }
+}
+
+/// Build the pointer chains of the custom indicators, at the same indices as
+/// in `settings.indicators`.
+fn resolve_custom_indicators(
+    settings: &Settings,
+    pointers: &Pointers,
+) -> Vec<Option<CustomIndicator>> {
+    settings
+        .indicators
+        .iter()
+        .map(|indicator| match &indicator.indicator {
+            IndicatorType::Custom(spec) => {
+                CustomIndicator::new(spec, pointers).map_err(|e| error!("{}", e)).ok()
+            },
+            _ => None,
+        })
+        .collect()
+}
+
 // Display some imgui debug information. Very expensive.
 fn imgui_debug(ui: &Ui) {
     let io = ui.io();
@@ -0,0 +1,129 @@
+//! In-game editor for hotkeys and cycle values.
+//!
+//! Edits are written to the config file through [`ConfigDocument`], and the
+//! tool then picks them up like any other change to the file.
+
+use std::path::{Path, PathBuf};
+
+use imgui::*;
+use libeldenring::prelude::*;
+
+use crate::config_writer::{ConfigDocument, Field, FieldKind};
+
+const POPUP_TAG: &str = "##settings_window";
+
+#[derive(Debug)]
+pub(crate) struct SettingsEditor {
+    path: Option<PathBuf>,
+    fields: Vec<Field>,
+    bufs: Vec<String>,
+    error: Option<String>,
+}
+
+impl SettingsEditor {
+    pub(crate) fn new(path: Option<PathBuf>) -> Self {
+        SettingsEditor { path, fields: Vec::new(), bufs: Vec::new(), error: None }
+    }
+
+    /// Read the current values from the config file and open the popup.
+    pub(crate) fn open(&mut self, ui: &Ui) {
+        let doc = self
+            .path
+            .as_deref()
+            .ok_or_else(|| "Couldn't find config file".to_string())
+            .and_then(ConfigDocument::load);
+
+        match doc {
+            Ok(doc) => {
+                self.fields = doc.fields();
+                self.bufs = self.fields.iter().map(|field| field.value.clone()).collect();
+                self.error = None;
+            },
+            Err(e) => {
+                self.fields.clear();
+                self.bufs.clear();
+                self.error = Some(e);
+            },
+        }
+
+        ui.open_popup(POPUP_TAG);
+    }
+
+    /// Write the edited fields to the config file, unless the file changed
+    /// under them since the popup was opened.
+    fn save(&self, path: &Path) -> Result<(), String> {
+        let mut doc = ConfigDocument::load(path)?;
+        doc.set_fields(
+            self.fields
+                .iter()
+                .zip(&self.bufs)
+                .filter(|(field, buf)| **buf != field.value)
+                .map(|(field, buf)| (field, buf.as_str())),
+        )?;
+        doc.save(path)
+    }
+
+    pub(crate) fn render(&mut self, ui: &Ui, cursor_show: &Bitflag<u8>) {
+        let Some(_token) = ui
+            .modal_popup_config(POPUP_TAG)
+            .resizable(false)
+            .movable(false)
+            .title_bar(false)
+            .begin_popup()
+        else {
+            return;
+        };
+
+        cursor_show.set(true);
+
+        ui.text(
+            "Hotkeys and cycle values from your config file.\nLeave a hotkey empty to remove it. \
+             Values\nare comma-separated lists. Commands from included\nfiles or from { group = \
+             ... } references aren't shown.",
+        );
+        ui.separator();
+
+        ui.child_window("##settings_fields").size([480., 400.]).build(|| {
+            let mut last_label = None;
+            for (i, (field, buf)) in self.fields.iter().zip(&mut self.bufs).enumerate() {
+                if last_label != Some(&field.label) {
+                    ui.text(&field.label);
+                    last_label = Some(&field.label);
+                }
+
+                let hint = match field.kind {
+                    FieldKind::Hotkey => "no hotkey",
+                    FieldKind::Floats | FieldKind::Ints => "",
+                };
+
+                ui.set_next_item_width(240.);
+                ui.input_text(format!("{}##settings_field_{i}", field.key), buf).hint(hint).build();
+            }
+        });
+
+        if let Some(e) = &self.error {
+            ui.text_colored([1., 0.3, 0.3, 1.], e);
+        }
+
+        ui.separator();
+
+        if let Some(path) = self.path.as_deref().filter(|_| !self.fields.is_empty()) {
+            if ui.button("Save") {
+                match self.save(path) {
+                    Ok(()) => {
+                        self.error = None;
+                        ui.close_current_popup();
+                        cursor_show.set(false);
+                    },
+                    Err(e) => self.error = Some(e),
+                }
+            }
+            ui.same_line();
+        }
+
+        if ui.button("Close") {
+            ui.close_current_popup();
+            cursor_show.set(false);
+        }
+    }
+}
@@ -0,0 +1,200 @@
+//! Player speed in units per second, independent of the framerate.
+//!
+//! Each frame's displacement is divided by that frame's duration, then
+//! smoothed with an exponential moving average whose time constant is given
+//! in seconds, so the same smoothing applies at 30, 60 or uncapped FPS.
+
+use std::collections::VecDeque;
+
+/// Displacements faster than this are teleports (warps, loads, respawns).
+const MAX_SPEED: f32 = 500.;
+const HISTORY_LEN: usize = 120;
+
+/// Duration of the last frame in seconds. The game's own framerate is
+/// preferred; the time measured by the overlay is the fallback.
+pub(crate) fn frame_time(fps: Option<f32>, delta_time: f32) -> f32 {
+    match fps {
+        Some(fps) if fps.is_finite() && fps > 1. => 1. / fps,
+        _ => delta_time,
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct Speedometer {
+    smoothing: f32,
+    peak_hold: f32,
+    last: Option<[f32; 3]>,
+    horizontal: f32,
+    vertical: f32,
+    // Peak horizontal speed and seconds since it was reached.
+    peak: f32,
+    peak_age: f32,
+    history: VecDeque<f32>,
+}
+
+impl Speedometer {
+    /// `smoothing` is the averaging time constant and `peak_hold` the time
+    /// a peak is displayed for, both in seconds.
+    pub(crate) fn new(smoothing: f32, peak_hold: f32) -> Self {
+        Speedometer {
+            smoothing: smoothing.max(0.),
+            peak_hold: peak_hold.max(0.),
+            last: None,
+            horizontal: 0.,
+            vertical: 0.,
+            peak: 0.,
+            peak_age: 0.,
+            history: VecDeque::with_capacity(HISTORY_LEN),
+        }
+    }
+
+    /// Feed the player's position and the frame duration in seconds.
+    pub(crate) fn update(&mut self, position: Option<[f32; 3]>, dt: f32) {
+        let Some(position) = position else {
+            self.last = None;
+            return;
+        };
+
+        let Some(last) = self.last.replace(position) else {
+            return;
+        };
+
+        // Nothing to measure on a paused frame.
+        if dt <= 0. || !dt.is_finite() {
+            return;
+        }
+
+        let [dx, dy, dz] = [position[0] - last[0], position[1] - last[1], position[2] - last[2]];
+        let horizontal = (dx * dx + dz * dz).sqrt() / dt;
+        let vertical = dy / dt;
+
+        if horizontal.hypot(vertical) > MAX_SPEED {
+            return;
+        }
+
+        let alpha = if self.smoothing > 0. { 1. - (-dt / self.smoothing).exp() } else { 1. };
+        self.horizontal += (horizontal - self.horizontal) * alpha;
+        self.vertical += (vertical - self.vertical) * alpha;
+
+        self.peak_age += dt;
+        if self.horizontal >= self.peak || self.peak_age > self.peak_hold {
+            self.peak = self.horizontal;
+            self.peak_age = 0.;
+        }
+
+        if self.history.len() >= HISTORY_LEN {
+            self.history.pop_front();
+        }
+        self.history.push_back(self.horizontal);
+    }
+
+    /// Smoothed speed on the XZ plane.
+    pub(crate) fn horizontal(&self) -> f32 {
+        self.horizontal
+    }
+
+    /// Smoothed speed along Y, positive upwards.
+    pub(crate) fn vertical(&self) -> f32 {
+        self.vertical
+    }
+
+    /// Highest horizontal speed over the peak hold time.
+    pub(crate) fn peak(&self) -> f32 {
+        self.peak
+    }
+
+    /// Recent horizontal speeds, oldest first.
+    pub(crate) fn history(&mut self) -> &[f32] {
+        self.history.make_contiguous()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(speedometer: &mut Speedometer, fps: f32, seconds: f32, velocity: [f32; 3]) {
+        let dt = 1. / fps;
+        let frames = (seconds * fps).round() as usize;
+        let mut position = speedometer.last.unwrap_or_default();
+        for _ in 0..frames {
+            position = [
+                position[0] + velocity[0] * dt,
+                position[1] + velocity[1] * dt,
+                position[2] + velocity[2] * dt,
+            ];
+            speedometer.update(Some(position), dt);
+        }
+    }
+
+    fn assert_close(a: f32, b: f32) {
+        assert!((a - b).abs() < 0.01, "{a} != {b}");
+    }
+
+    #[test]
+    fn test_frame_time() {
+        assert_close(frame_time(Some(60.), 0.1), 1. / 60.);
+        assert_close(frame_time(None, 0.1), 0.1);
+        assert_close(frame_time(Some(0.), 0.1), 0.1);
+        assert_close(frame_time(Some(f32::NAN), 0.1), 0.1);
+    }
+
+    #[test]
+    fn test_framerate_independent() {
+        for fps in [30., 60., 144., 240.] {
+            let mut speedometer = Speedometer::new(0., 0.);
+            speedometer.update(Some([0.; 3]), 0.);
+            run(&mut speedometer, fps, 1., [3., -2., 4.]);
+            assert_close(speedometer.horizontal(), 5.);
+            assert_close(speedometer.vertical(), -2.);
+        }
+    }
+
+    #[test]
+    fn test_smoothing() {
+        let mut at_30 = Speedometer::new(0.2, 0.);
+        let mut at_144 = Speedometer::new(0.2, 0.);
+        at_30.update(Some([0.; 3]), 0.);
+        at_144.update(Some([0.; 3]), 0.);
+
+        // After one time constant, about 63% of a step change shows up at
+        // any framerate.
+        run(&mut at_30, 30., 0.2, [10., 0., 0.]);
+        run(&mut at_144, 144., 0.2, [10., 0., 0.]);
+        assert!((at_30.horizontal() - 6.32).abs() < 0.1);
+        assert!((at_144.horizontal() - 6.32).abs() < 0.1);
+
+        run(&mut at_30, 30., 2., [10., 0., 0.]);
+        assert_close(at_30.horizontal(), 10.);
+    }
+
+    #[test]
+    fn test_peak_hold() {
+        let mut speedometer = Speedometer::new(0., 1.);
+        speedometer.update(Some([0.; 3]), 0.);
+
+        run(&mut speedometer, 60., 0.5, [8., 0., 0.]);
+        run(&mut speedometer, 60., 0.5, [2., 0., 0.]);
+        assert_close(speedometer.peak(), 8.);
+
+        run(&mut speedometer, 60., 0.6, [2., 0., 0.]);
+        assert_close(speedometer.peak(), 2.);
+    }
+
+    #[test]
+    fn test_teleport_and_pause() {
+        let mut speedometer = Speedometer::new(0., 0.);
+        speedometer.update(Some([0.; 3]), 0.);
+        run(&mut speedometer, 60., 0.5, [4., 0., 0.]);
+
+        speedometer.update(Some([1000., 0., 0.]), 1. / 60.);
+        assert_close(speedometer.horizontal(), 4.);
+
+        speedometer.update(Some([1000., 0., 0.]), 0.);
+        assert_close(speedometer.horizontal(), 4.);
+
+        speedometer.update(None, 1. / 60.);
+        speedometer.update(Some([0.; 3]), 1. / 60.);
+        assert_close(speedometer.horizontal(), 4.);
+    }
+}
@@ -0,0 +1,230 @@
+//! Minimal `{name}` substitution for user-provided templates.
+//!
+//! `{{` and `}}` produce literal braces. Placeholders that don't name a known
+//! value, or whose format spec is invalid, are left untouched so that typos
+//! are visible in the output.
+//!
+//! A placeholder may carry a subset of Rust's format spec after a colon:
+//! `[[fill]align][0][width][.precision]`, e.g. `{minutes:02}` or `{x:.1}`.
+//! Values are strings, so the precision reformats values that parse as
+//! numbers and truncates any other value.
+
+use std::collections::BTreeMap;
+
+pub(crate) type Values = BTreeMap<&'static str, String>;
+
+pub(crate) fn render(template: &str, values: &Values) -> String {
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+
+    while let Some(i) = rest.find(['{', '}']) {
+        out.push_str(&rest[..i]);
+        rest = &rest[i..];
+
+        if rest.starts_with("{{") || rest.starts_with("}}") {
+            out.push_str(&rest[..1]);
+            rest = &rest[2..];
+            continue;
+        }
+
+        let placeholder = rest.strip_prefix('{').and_then(|r| r.find('}').map(|j| &r[..j]));
+        match placeholder {
+            Some(placeholder) => {
+                let (name, spec) = placeholder.split_once(':').unwrap_or((placeholder, ""));
+                let formatted = values.get(name).and_then(|value| format_value(value, spec));
+                match formatted {
+                    Some(value) => out.push_str(&value),
+                    None => out.push_str(&rest[..placeholder.len() + 2]),
+                }
+                rest = &rest[placeholder.len() + 2..];
+            },
+            None => {
+                out.push_str(&rest[..1]);
+                rest = &rest[1..];
+            },
+        }
+    }
+
+    out.push_str(rest);
+    out
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Align {
+    Left,
+    Center,
+    Right,
+}
+
+#[derive(Debug, Default, PartialEq)]
+struct Spec {
+    fill: Option<char>,
+    align: Option<Align>,
+    zero: bool,
+    width: usize,
+    precision: Option<usize>,
+}
+
+fn parse_align(c: char) -> Option<Align> {
+    match c {
+        '<' => Some(Align::Left),
+        '^' => Some(Align::Center),
+        '>' => Some(Align::Right),
+        _ => None,
+    }
+}
+
+fn parse_spec(spec: &str) -> Option<Spec> {
+    let mut result = Spec::default();
+    let mut rest = spec;
+
+    let mut chars = rest.chars();
+    match (chars.next(), chars.next().and_then(parse_align)) {
+        (Some(fill), Some(align)) => {
+            result.fill = Some(fill);
+            result.align = Some(align);
+            rest = &rest[fill.len_utf8() + 1..];
+        },
+        (Some(c), _) if parse_align(c).is_some() => {
+            result.align = parse_align(c);
+            rest = &rest[1..];
+        },
+        _ => {},
+    }
+
+    if let Some(r) = rest.strip_prefix('0') {
+        result.zero = true;
+        rest = r;
+    }
+
+    let (width, precision) = match rest.split_once('.') {
+        Some((width, precision)) => (width, Some(precision)),
+        None => (rest, None),
+    };
+
+    if !width.is_empty() {
+        result.width = width.parse().ok()?;
+    }
+
+    if let Some(precision) = precision {
+        result.precision = Some(precision.parse().ok()?);
+    }
+
+    Some(result)
+}
+
+/// Apply a format spec to a value, or return `None` if the spec is invalid.
+fn format_value(value: &str, spec: &str) -> Option<String> {
+    if spec.is_empty() {
+        return Some(value.to_string());
+    }
+
+    let spec = parse_spec(spec)?;
+    let number = value.parse::<f64>().ok();
+
+    let mut value = match (spec.precision, number) {
+        (Some(precision), Some(number)) => format!("{number:.precision$}"),
+        (Some(precision), None) => value.chars().take(precision).collect(),
+        (None, _) => value.to_string(),
+    };
+
+    let len = value.chars().count();
+    if len >= spec.width {
+        return Some(value);
+    }
+    let padding = spec.width - len;
+
+    // Zero padding goes between the sign and the digits, as in Rust.
+    if spec.zero && spec.align.is_none() && number.is_some() {
+        let sign = if value.starts_with(['-', '+']) { 1 } else { 0 };
+        value.insert_str(sign, &"0".repeat(padding));
+        return Some(value);
+    }
+
+    let fill = spec.fill.unwrap_or(' ');
+    let align = spec.align.unwrap_or(if number.is_some() { Align::Right } else { Align::Left });
+    let (before, after) = match align {
+        Align::Left => (0, padding),
+        Align::Center => (padding / 2, padding - padding / 2),
+        Align::Right => (padding, 0),
+    };
+
+    let mut out = String::with_capacity(value.len() + padding);
+    out.extend(std::iter::repeat_n(fill, before));
+    out.push_str(&value);
+    out.extend(std::iter::repeat_n(fill, after));
+    Some(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn values() -> Values {
+        let mut values = Values::new();
+        values.insert("hours", "1".to_string());
+        values.insert("minutes", "5".to_string());
+        values.insert("x", "-12.3456".to_string());
+        values.insert("map", "m60_42_36_00".to_string());
+        values
+    }
+
+    #[test]
+    fn test_substitution() {
+        let values = values();
+        assert_eq!(render("IGT {hours}:{minutes}", &values), "IGT 1:5");
+        assert_eq!(render("{{hours}} }}{{", &values), "{hours} }{");
+        assert_eq!(render("{unknown} {map}", &values), "{unknown} m60_42_36_00");
+        assert_eq!(render("{hours", &values), "{hours");
+        assert_eq!(render("", &values), "");
+    }
+
+    #[test]
+    fn test_numbers() {
+        let values = values();
+        assert_eq!(render("{hours}:{minutes:02}", &values), "1:05");
+        assert_eq!(render("{x:.1}", &values), "-12.3");
+        assert_eq!(render("{x:.0}", &values), "-12");
+        assert_eq!(render("{x:09.2}", &values), "-00012.35");
+        assert_eq!(render("[{minutes:4}]", &values), "[   5]");
+        assert_eq!(render("[{minutes:<4}]", &values), "[5   ]");
+    }
+
+    #[test]
+    fn test_strings() {
+        let values = values();
+        assert_eq!(render("[{map:14}]", &values), "[m60_42_36_00  ]");
+        assert_eq!(render("[{map:>14}]", &values), "[  m60_42_36_00]");
+        assert_eq!(render("[{map:*^16}]", &values), "[**m60_42_36_00**]");
+        assert_eq!(render("{map:.3}", &values), "m60");
+        // Zero padding only applies to numbers.
+        assert_eq!(render("[{map:014}]", &values), "[m60_42_36_00  ]");
+    }
+
+    #[test]
+    fn test_invalid_spec() {
+        let values = values();
+        assert_eq!(render("{minutes:x}", &values), "{minutes:x}");
+        assert_eq!(render("{minutes:.}", &values), "{minutes:.}");
+        assert_eq!(render("{minutes:2.1.1}", &values), "{minutes:2.1.1}");
+    }
+
+    #[test]
+    fn test_parse_spec() {
+        assert_eq!(parse_spec(""), Some(Spec::default()));
+        assert_eq!(
+            parse_spec("_>08.3"),
+            Some(Spec {
+                fill: Some('_'),
+                align: Some(Align::Right),
+                zero: true,
+                width: 8,
+                precision: Some(3),
+            })
+        );
+        assert_eq!(
+            parse_spec("^5"),
+            Some(Spec { align: Some(Align::Center), width: 5, ..Default::default() })
+        );
+    }
+}
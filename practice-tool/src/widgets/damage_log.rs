@@ -0,0 +1,344 @@
+use std::collections::VecDeque;
+use std::fmt::Write as _;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use libeldenring::prelude::*;
+use practice_tool_core::crossbeam_channel::Sender;
+use practice_tool_core::key::Key;
+use practice_tool_core::widgets::{scaling_factor, Widget, BUTTON_HEIGHT, BUTTON_WIDTH};
+
+use super::target::{locked_on_entity, EntityPointerChains};
+use crate::util;
+
+const MAX_ENTRIES: usize = 10_000;
+const SHOWN_ENTRIES: usize = 8;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Direction {
+    Dealt,
+    Received,
+}
+
+#[derive(Debug)]
+struct Hit {
+    time: Instant,
+    igt: Option<usize>,
+    direction: Direction,
+    damage: u32,
+    attacker_anim: Option<u32>,
+}
+
+#[derive(Debug)]
+pub(crate) struct DamageLog {
+    label: String,
+    hotkey: Option<Key>,
+    is_enabled: bool,
+    dps_window: Duration,
+
+    player_points: PointerChain<CharacterPoints>,
+    player_anim: PointerChain<u32>,
+    igt: PointerChain<usize>,
+
+    last_player_hp: Option<(i32, i32)>,
+    last_target_hp: Option<(u64, u32, u32)>,
+    hits: VecDeque<Hit>,
+
+    logs: Vec<String>,
+    text: String,
+}
+
+impl DamageLog {
+    pub(crate) fn new(
+        player_points: PointerChain<CharacterPoints>,
+        player_anim: PointerChain<u32>,
+        igt: PointerChain<usize>,
+        dps_window: f32,
+        hotkey: Option<Key>,
+    ) -> Self {
+        DamageLog {
+            label: hotkey
+                .map(|k| format!("Damage log ({k})"))
+                .unwrap_or_else(|| "Damage log".to_string()),
+            hotkey,
+            is_enabled: false,
+            dps_window: Duration::from_secs_f32(dps_window.max(0.1)),
+            player_points,
+            player_anim,
+            igt,
+            last_player_hp: None,
+            last_target_hp: None,
+            hits: VecDeque::new(),
+            logs: Vec::new(),
+            text: String::new(),
+        }
+    }
+
+    fn sample(&mut self) {
+        let now = Instant::now();
+        let igt = self.igt.read();
+        let target = locked_on_entity().map(|addr| (addr, EntityPointerChains::new(addr)));
+
+        // Damage received: the attacker is assumed to be the locked-on target.
+        let player_hp = self.player_points.read().map(|p| (p.hp, p.max_hp));
+        if let (Some((last_hp, last_max_hp)), Some((hp, max_hp))) =
+            (self.last_player_hp, player_hp)
+        {
+            if max_hp == last_max_hp && hp < last_hp {
+                let attacker_anim = target.as_ref().and_then(|(_, epc)| epc.anim.read());
+                self.push(Hit {
+                    time: now,
+                    igt,
+                    direction: Direction::Received,
+                    damage: (last_hp - hp) as u32,
+                    attacker_anim,
+                });
+            }
+        }
+        self.last_player_hp = player_hp;
+
+        // Damage dealt: only counted while the same entity stays locked on.
+        let target_hp = target
+            .as_ref()
+            .and_then(|(addr, epc)| epc.hp.read().map(|[hp, _, max_hp]| (*addr, hp, max_hp)));
+        if let (Some((last_addr, last_hp, last_max_hp)), Some((addr, hp, max_hp))) =
+            (self.last_target_hp, target_hp)
+        {
+            if addr == last_addr && max_hp == last_max_hp && hp < last_hp {
+                self.push(Hit {
+                    time: now,
+                    igt,
+                    direction: Direction::Dealt,
+                    damage: last_hp - hp,
+                    attacker_anim: self.player_anim.read(),
+                });
+            }
+        }
+        self.last_target_hp = target_hp;
+    }
+
+    fn push(&mut self, hit: Hit) {
+        if self.hits.len() >= MAX_ENTRIES {
+            self.hits.pop_front();
+        }
+        self.hits.push_back(hit);
+    }
+
+    fn export_csv(&mut self) {
+        let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+        let Some(path) = util::get_dll_path()
+            .and_then(|path| path.parent().map(|p| p.join(format!("damage_log_{timestamp}.csv"))))
+        else {
+            self.logs.push("Couldn't locate the DLL directory".to_string());
+            return;
+        };
+
+        match std::fs::write(&path, csv(&self.hits, self.dps_window)) {
+            Ok(()) => self.logs.push(format!("Damage log exported to {}", path.display())),
+            Err(e) => self.logs.push(format!("Couldn't export damage log: {e}")),
+        }
+    }
+
+    fn toggle(&mut self) {
+        self.is_enabled = !self.is_enabled;
+        self.last_player_hp = None;
+        self.last_target_hp = None;
+    }
+}
+
+impl Widget for DamageLog {
+    fn render(&mut self, ui: &imgui::Ui) {
+        let scale = scaling_factor(ui);
+        let button_width = BUTTON_WIDTH * scale;
+
+        let mut state = self.is_enabled;
+        if ui.checkbox(&self.label, &mut state) {
+            self.toggle();
+        }
+
+        if ui.button_with_size("Export damage log to CSV", [button_width, BUTTON_HEIGHT]) {
+            self.export_csv();
+        }
+
+        if ui.button_with_size("Clear damage log", [button_width, BUTTON_HEIGHT]) {
+            self.hits.clear();
+        }
+    }
+
+    fn render_closed(&mut self, ui: &imgui::Ui) {
+        if !self.is_enabled {
+            return;
+        }
+
+        let dps = dps(&self.hits, self.dps_window, Instant::now());
+        self.text.clear();
+        write!(self.text, "DPS ({:.0}s) {dps:.1}", self.dps_window.as_secs_f32()).ok();
+        ui.text(&self.text);
+
+        for hit in self.hits.iter().rev().take(SHOWN_ENTRIES) {
+            self.text.clear();
+            let igt = hit.igt.unwrap_or_default();
+            let direction = match hit.direction {
+                Direction::Dealt => "dealt",
+                Direction::Received => "taken",
+            };
+            write!(
+                self.text,
+                "{:02}:{:02}.{:03} {direction} {:<6}",
+                igt / 60_000,
+                igt / 1000 % 60,
+                igt % 1000,
+                hit.damage
+            )
+            .ok();
+            if let Some(anim) = hit.attacker_anim {
+                write!(self.text, " anim {anim}").ok();
+            }
+
+            match hit.direction {
+                Direction::Dealt => ui.text(&self.text),
+                Direction::Received => ui.text_colored([1.0, 0.4, 0.4, 1.0], &self.text),
+            }
+        }
+    }
+
+    fn interact(&mut self, ui: &imgui::Ui) {
+        if self.hotkey.map(|k| k.is_pressed(ui)).unwrap_or(false) {
+            self.toggle();
+        }
+
+        if self.is_enabled {
+            self.sample();
+        }
+    }
+
+    fn action(&mut self) {
+        self.toggle();
+    }
+
+    fn log(&mut self, tx: Sender<String>) {
+        for log in self.logs.drain(..) {
+            tx.send(log).ok();
+        }
+    }
+}
+
+/// Damage dealt per second over the `window` before `at`.
+fn dps(hits: &VecDeque<Hit>, window: Duration, at: Instant) -> f32 {
+    let damage: u32 = hits
+        .iter()
+        .rev()
+        .filter(|hit| hit.time <= at)
+        .take_while(|hit| at.duration_since(hit.time) <= window)
+        .filter(|hit| hit.direction == Direction::Dealt)
+        .map(|hit| hit.damage)
+        .sum();
+
+    damage as f32 / window.as_secs_f32()
+}
+
+/// The hits as CSV, each with the DPS at the time it landed.
+fn csv(hits: &VecDeque<Hit>, window: Duration) -> String {
+    let dealt = |hit: &Hit| if hit.direction == Direction::Dealt { hit.damage } else { 0 };
+
+    let mut csv = String::from("igt_ms,direction,damage,attacker_animation,dps\n");
+
+    // Damage dealt by the hits in `start..end`. Hits are in time order, so
+    // both ends of the window only move forward.
+    let (mut start, mut end, mut damage) = (0, 0, 0);
+    for hit in hits {
+        while end < hits.len() && hits[end].time <= hit.time {
+            damage += dealt(&hits[end]);
+            end += 1;
+        }
+        while hit.time.duration_since(hits[start].time) > window {
+            damage -= dealt(&hits[start]);
+            start += 1;
+        }
+
+        let direction = match hit.direction {
+            Direction::Dealt => "dealt",
+            Direction::Received => "received",
+        };
+        writeln!(
+            csv,
+            "{},{direction},{},{},{:.1}",
+            hit.igt.map(|igt| igt.to_string()).unwrap_or_default(),
+            hit.damage,
+            hit.attacker_anim.map(|anim| anim.to_string()).unwrap_or_default(),
+            damage as f32 / window.as_secs_f32(),
+        )
+        .ok();
+    }
+
+    csv
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WINDOW: Duration = Duration::from_secs(5);
+
+    fn hits(start: Instant, hits: &[(u64, Direction, u32)]) -> VecDeque<Hit> {
+        hits.iter()
+            .map(|&(millis, direction, damage)| Hit {
+                time: start + Duration::from_millis(millis),
+                igt: Some(60_000 + millis as usize),
+                direction,
+                damage,
+                attacker_anim: (direction == Direction::Received).then_some(3000000),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_dps() {
+        let start = Instant::now();
+        let hits = hits(start, &[
+            (0, Direction::Dealt, 500),
+            (1000, Direction::Received, 300),
+            (2000, Direction::Dealt, 250),
+            (7000, Direction::Dealt, 100),
+        ]);
+        let at = |millis| start + Duration::from_millis(millis);
+
+        assert_eq!(dps(&hits, WINDOW, at(0)), 100.);
+        assert_eq!(dps(&hits, WINDOW, at(2000)), 150.);
+        // The window includes hits exactly at its start.
+        assert_eq!(dps(&hits, WINDOW, at(5000)), 150.);
+        assert_eq!(dps(&hits, WINDOW, at(5001)), 50.);
+        assert_eq!(dps(&hits, WINDOW, at(7000)), 70.);
+        assert_eq!(dps(&hits, WINDOW, at(20000)), 0.);
+        assert_eq!(dps(&VecDeque::new(), WINDOW, at(0)), 0.);
+    }
+
+    #[test]
+    fn test_csv() {
+        let start = Instant::now();
+        let mut hits = hits(start, &[
+            (0, Direction::Dealt, 500),
+            (1000, Direction::Received, 300),
+            (2000, Direction::Dealt, 250),
+            (2000, Direction::Dealt, 50),
+            (7000, Direction::Dealt, 100),
+            (12001, Direction::Dealt, 10),
+        ]);
+        hits[1].igt = None;
+
+        assert_eq!(csv(&hits, WINDOW).lines().collect::<Vec<_>>(), [
+            "igt_ms,direction,damage,attacker_animation,dps",
+            "60000,dealt,500,,100.0",
+            ",received,300,3000000,100.0",
+            // Both hits landing on the same frame count for each other.
+            "62000,dealt,250,,160.0",
+            "62000,dealt,50,,160.0",
+            "67000,dealt,100,,80.0",
+            "72001,dealt,10,,2.0",
+        ]);
+
+        // Same as computing the DPS of each hit on its own.
+        for (hit, row) in hits.iter().zip(csv(&hits, WINDOW).lines().skip(1)) {
+            assert!(row.ends_with(&format!(",{:.1}", dps(&hits, WINDOW, hit.time))), "{row}");
+        }
+    }
+}
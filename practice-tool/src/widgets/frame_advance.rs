@@ -0,0 +1,249 @@
+//! Pause the game and advance it a few frames at a time.
+//!
+//! Pausing sets the player's and Torrent's animation speeds to zero, so only
+//! those two are frozen: enemies, projectiles and effects keep running.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use libeldenring::prelude::*;
+use practice_tool_core::key::Key;
+use practice_tool_core::widgets::{scaling_factor, Widget, BUTTON_HEIGHT, BUTTON_WIDTH};
+
+static PAUSED: AtomicBool = AtomicBool::new(false);
+
+/// Whether the game is currently held by the frame advance widget. The frame
+/// counter doesn't count frames while this is set.
+pub(crate) fn is_paused() -> bool {
+    PAUSED.load(Ordering::Relaxed)
+}
+
+/// Pause, step and resume transitions. Each returns the speed to set.
+#[derive(Debug)]
+struct PauseState {
+    paused: bool,
+    // Speed restored when the game runs again.
+    resume_speed: f32,
+    // ImGui frame on which the game is paused again after a step.
+    stepping_until: Option<i32>,
+}
+
+impl Default for PauseState {
+    fn default() -> Self {
+        PauseState { paused: false, resume_speed: 1., stepping_until: None }
+    }
+}
+
+impl PauseState {
+    fn pause(&mut self, speed: Option<f32>) -> f32 {
+        if let Some(speed) = speed.filter(|&speed| speed > 0.) {
+            self.resume_speed = speed;
+        }
+        self.paused = true;
+        self.stepping_until = None;
+        0.
+    }
+
+    fn resume(&mut self) -> f32 {
+        self.paused = false;
+        self.stepping_until = None;
+        self.resume_speed
+    }
+
+    /// Run the game until `frames` frames after `frame`, pausing it first if
+    /// it's running.
+    fn step(&mut self, speed: Option<f32>, frame: i32, frames: u32) -> f32 {
+        if !self.paused {
+            self.pause(speed);
+        }
+        self.stepping_until = Some(frame + frames as i32);
+        self.resume_speed
+    }
+
+    /// Pause again once the step is over.
+    fn tick(&mut self, frame: i32) -> Option<f32> {
+        if self.stepping_until.is_some_and(|until| frame >= until) {
+            self.stepping_until = None;
+            Some(0.)
+        } else {
+            None
+        }
+    }
+
+    /// Whether the game is held still, rather than running or stepping.
+    fn is_holding(&self) -> bool {
+        self.paused && self.stepping_until.is_none()
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct FrameAdvance {
+    speed: [PointerChain<f32>; 2],
+    frames: u32,
+    hotkey_pause: Option<Key>,
+    hotkey_step: Option<Key>,
+    label_pause: String,
+    label_step: String,
+    state: PauseState,
+}
+
+impl FrameAdvance {
+    pub(crate) fn new(
+        speed: [PointerChain<f32>; 2],
+        frames: u32,
+        hotkey_pause: Option<Key>,
+        hotkey_step: Option<Key>,
+    ) -> Self {
+        let frames = frames.max(1);
+        let label_pause = match hotkey_pause {
+            Some(k) => format!("Pause game ({k})"),
+            None => "Pause game".to_string(),
+        };
+        let frame_label = if frames == 1 { "frame" } else { "frames" };
+        let label_step = match hotkey_step {
+            Some(k) => format!("Advance {frames} {frame_label} ({k})"),
+            None => format!("Advance {frames} {frame_label}"),
+        };
+
+        FrameAdvance {
+            speed,
+            frames,
+            hotkey_pause,
+            hotkey_step,
+            label_pause,
+            label_step,
+            state: PauseState::default(),
+        }
+    }
+
+    fn set_speed(&self, speed: f32) {
+        for ptr in &self.speed {
+            ptr.write(speed);
+        }
+        PAUSED.store(self.state.is_holding(), Ordering::Relaxed);
+    }
+
+    fn toggle(&mut self) {
+        let speed = if self.state.paused {
+            self.state.resume()
+        } else {
+            self.state.pause(self.speed[0].read())
+        };
+        self.set_speed(speed);
+    }
+
+    fn step(&mut self, ui: &imgui::Ui) {
+        let speed = self.state.step(self.speed[0].read(), ui.frame_count(), self.frames);
+        self.set_speed(speed);
+    }
+
+    // Called from every render path, as interact is skipped while typing in
+    // the menu.
+    fn tick(&mut self, ui: &imgui::Ui) {
+        if let Some(speed) = self.state.tick(ui.frame_count()) {
+            self.set_speed(speed);
+        }
+    }
+}
+
+impl Widget for FrameAdvance {
+    fn render(&mut self, ui: &imgui::Ui) {
+        self.tick(ui);
+
+        let scale = scaling_factor(ui);
+
+        let mut state = self.state.paused;
+        if ui.checkbox(&self.label_pause, &mut state) {
+            self.toggle();
+        }
+
+        if ui.button_with_size(&self.label_step, [BUTTON_WIDTH * scale, BUTTON_HEIGHT]) {
+            self.step(ui);
+        }
+    }
+
+    fn render_closed(&mut self, ui: &imgui::Ui) {
+        self.tick(ui);
+    }
+
+    fn interact(&mut self, ui: &imgui::Ui) {
+        self.tick(ui);
+
+        if ui.is_any_item_active() {
+            return;
+        }
+
+        if self.hotkey_pause.map(|k| k.is_pressed(ui)).unwrap_or(false) {
+            self.toggle();
+        }
+
+        if self.hotkey_step.map(|k| k.is_pressed(ui)).unwrap_or(false) {
+            self.step(ui);
+        }
+    }
+
+    fn action(&mut self) {
+        self.toggle();
+    }
+}
+
+// Don't leave the game frozen when the widget goes away on a config reload.
+impl Drop for FrameAdvance {
+    fn drop(&mut self) {
+        if self.state.paused {
+            let speed = self.state.resume();
+            self.set_speed(speed);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pause_resume() {
+        let mut state = PauseState::default();
+
+        assert_eq!(state.pause(Some(1.5)), 0.);
+        assert!(state.paused && state.is_holding());
+        assert_eq!(state.resume(), 1.5);
+        assert!(!state.paused && !state.is_holding());
+
+        // A game that's already still, or whose speed can't be read, resumes
+        // at the last known speed.
+        state.pause(Some(0.));
+        assert_eq!(state.resume(), 1.5);
+        state.pause(None);
+        assert_eq!(state.resume(), 1.5);
+    }
+
+    #[test]
+    fn test_step() {
+        let mut state = PauseState::default();
+
+        // Stepping a running game pauses it first.
+        assert_eq!(state.step(Some(2.), 100, 3), 2.);
+        assert!(state.paused && !state.is_holding());
+        assert_eq!(state.tick(101), None);
+        assert_eq!(state.tick(102), None);
+        assert_eq!(state.tick(103), Some(0.));
+        assert!(state.is_holding());
+        assert_eq!(state.tick(104), None);
+
+        // Stepping while paused keeps the speed from before the pause.
+        assert_eq!(state.step(Some(0.), 200, 1), 2.);
+        assert_eq!(state.tick(201), Some(0.));
+
+        // Resuming in the middle of a step doesn't pause again later.
+        state.step(Some(0.), 300, 5);
+        assert_eq!(state.resume(), 2.);
+        assert_eq!(state.tick(305), None);
+        assert!(!state.paused);
+
+        // Pausing in the middle of a step holds the game right away.
+        state.step(Some(2.), 500, 5);
+        assert_eq!(state.pause(Some(2.)), 0.);
+        assert!(state.is_holding());
+        assert_eq!(state.tick(505), None);
+    }
+}
@@ -232,6 +232,28 @@ impl ItemSpawner<'_> {
         }
     }
 
+    /// Spawn `qty` of the item with the given full ID, upgrade and affinity
+    /// included, without touching the current selection.
+    pub(crate) fn spawn_item(&mut self, item_id: u32, qty: u32) {
+        if self.sentinel.get().is_none() {
+            self.write_log("Not spawning item when not in game".into());
+            return;
+        }
+
+        let i = ItemSpawnInstance {
+            spawn_item_func_ptr: self.func_ptr as _,
+            map_item_man: self.map_item_man as _,
+            qty,
+            item_id,
+        };
+
+        self.write_log(format!("Spawning {i}"));
+
+        unsafe {
+            i.spawn();
+        }
+    }
+
     fn write_log(&mut self, log: String) {
         self.logs.push(log);
     }
@@ -1,8 +1,10 @@
 pub(crate) mod character_stats;
 pub(crate) mod cycle_color;
 pub(crate) mod cycle_speed;
+pub(crate) mod damage_log;
 pub(crate) mod deathcam;
 pub(crate) mod flag;
+pub(crate) mod frame_advance;
 pub(crate) mod group;
 pub(crate) mod item_spawn;
 pub(crate) mod label;
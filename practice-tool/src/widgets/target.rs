@@ -1,5 +1,12 @@
+mod edit;
+mod poise;
+mod status;
+mod tracker;
+
 use std::f32::consts::PI;
 use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::time::Instant;
 
 use imgui::sys::{igGetCursorPosX, igGetCursorPosY, igGetWindowPos, ImVec2};
 use imgui::{ProgressBar, StyleColor};
@@ -12,6 +19,11 @@ use windows::Win32::System::Memory::{
     VirtualAlloc, MEM_COMMIT, MEM_RESERVE, PAGE_EXECUTE_READWRITE,
 };
 
+use self::edit::TargetEditor;
+use self::poise::{PoiseSample, PoiseTracker};
+use self::status::StatusTracker;
+use self::tracker::EntityTracker;
+
 #[derive(Debug, Default)]
 struct EnemyInfo {
     hp: u32,
@@ -44,6 +56,22 @@ struct EnemyResistances {
     mad_max: u32,
 }
 
+impl EnemyResistances {
+    /// Current and maximum buildup of each status, in display order, which
+    /// is also the order of the buildups in memory.
+    fn meters(&self) -> [(u32, u32); 7] {
+        [
+            (self.poison, self.poison_max),
+            (self.rot, self.rot_max),
+            (self.bleed, self.bleed_max),
+            (self.blight, self.blight_max),
+            (self.frost, self.frost_max),
+            (self.sleep, self.sleep_max),
+            (self.mad, self.mad_max),
+        ]
+    }
+}
+
 #[derive(Debug, Default)]
 #[repr(C)]
 struct PoiseMeter {
@@ -65,15 +93,60 @@ struct EntityPosition {
     z: f32,
 }
 
-struct EntityPointerChains {
-    hp: PointerChain<[u32; 3]>,
-    sp: PointerChain<[u32; 3]>,
-    mp: PointerChain<[u32; 3]>,
+// Written by the detour installed in `Target::enable`. Lives in a static so
+// that other modules can inspect the locked-on entity too.
+static ENTITY_ADDR: AtomicU64 = AtomicU64::new(0);
+
+// Trampoline the detour jumps to. Allocated by the first `Target` and reused
+// by the ones built on config reloads: it can't be freed, as a game thread may
+// still be running it.
+static TRAMPOLINE_ADDR: AtomicUsize = AtomicUsize::new(0);
+
+/// Address of the currently locked-on entity, if the target widget is enabled
+/// and something has been locked on.
+pub(crate) fn locked_on_entity() -> Option<u64> {
+    match ENTITY_ADDR.load(Ordering::Relaxed) {
+        0 => None,
+        addr => Some(addr),
+    }
+}
+
+pub(crate) struct EntityPointerChains {
+    entity_addr: usize,
+    pub(crate) npc_param_id: PointerChain<u32>,
+    pub(crate) hp: PointerChain<[u32; 3]>,
+    pub(crate) sp: PointerChain<[u32; 3]>,
+    pub(crate) mp: PointerChain<[u32; 3]>,
+    pub(crate) anim: PointerChain<u32>,
     res: PointerChain<EnemyResistances>,
     poise: PointerChain<PoiseMeter>,
     position: PointerChain<EntityPosition>,
 }
 
+impl EntityPointerChains {
+    pub(crate) fn new(entity_addr: u64) -> Self {
+        let entity_addr = entity_addr as usize;
+
+        EntityPointerChains {
+            entity_addr,
+            npc_param_id: pointer_chain!(entity_addr + 0x60),
+            hp: pointer_chain!(entity_addr + 0x190, 0, 0x138),
+            sp: pointer_chain!(entity_addr + 0x190, 0, 0x154),
+            mp: pointer_chain!(entity_addr + 0x190, 0, 0x148),
+            anim: pointer_chain!(entity_addr + 0x190, 0x18, 0x20),
+            res: pointer_chain!(entity_addr + 0x190, 0x20, 0x10),
+            poise: pointer_chain!(entity_addr + 0x190, 0x40, 0x10),
+            position: pointer_chain!(entity_addr + 0x190, 0x68, 0x54),
+        }
+    }
+
+    /// Current buildup of the status at `index` in `EnemyResistances`, to
+    /// write it without touching the others.
+    fn buildup(&self, index: usize) -> PointerChain<u32> {
+        pointer_chain!(self.entity_addr + 0x190, 0x20, 0x10 + index * std::mem::size_of::<u32>())
+    }
+}
+
 #[derive(Debug)]
 pub(crate) struct Target {
     label: String,
@@ -81,11 +154,17 @@ pub(crate) struct Target {
     detour_addr: PointerChain<[u8; 11]>,
     detour_orig_data: [u8; 11],
     hotkey: Option<Key>,
+    hotkey_pin: Option<Key>,
     is_enabled: bool,
-    entity_addr: u64,
     player_position: ErPosition,
+    tracker: EntityTracker,
+    editor: TargetEditor,
+    tracked_entity: Option<u64>,
+    status: StatusTracker,
+    poise: PoiseTracker,
 
     distance_text: String,
+    poise_text: String,
 }
 
 unsafe impl Send for Target {}
@@ -96,25 +175,35 @@ impl Target {
         detour_addr: PointerChain<u64>,
         player_position: ErPosition,
         hotkey: Option<Key>,
+        hotkey_pin: Option<Key>,
+        track: usize,
     ) -> Self {
         let detour_addr = detour_addr.cast();
-        let mut allocate_near = detour_addr.eval().unwrap() as usize;
-
-        let alloc_addr = loop {
-            let c = unsafe {
-                VirtualAlloc(
-                    Some(allocate_near as *mut _),
-                    0x20,
-                    MEM_COMMIT | MEM_RESERVE,
-                    PAGE_EXECUTE_READWRITE,
-                )
-            };
-            if c.is_null() {
-                allocate_near += 65536;
-            } else {
-                break pointer_chain!(c as usize);
-            }
+
+        let alloc_addr = match TRAMPOLINE_ADDR.load(Ordering::Relaxed) {
+            0 => {
+                let mut allocate_near = detour_addr.eval().unwrap() as usize;
+                let c = loop {
+                    let c = unsafe {
+                        VirtualAlloc(
+                            Some(allocate_near as *mut _),
+                            0x20,
+                            MEM_COMMIT | MEM_RESERVE,
+                            PAGE_EXECUTE_READWRITE,
+                        )
+                    };
+                    if c.is_null() {
+                        allocate_near += 65536;
+                    } else {
+                        break c as usize;
+                    }
+                };
+                TRAMPOLINE_ADDR.store(c, Ordering::Relaxed);
+                c
+            },
+            c => c,
         };
+        let alloc_addr = pointer_chain!(alloc_addr);
 
         Target {
             label: hotkey
@@ -124,27 +213,26 @@ impl Target {
             detour_addr,
             detour_orig_data: Default::default(),
             hotkey,
+            hotkey_pin,
             is_enabled: false,
-            entity_addr: 0,
             player_position,
+            tracker: EntityTracker::new(track),
+            editor: TargetEditor::default(),
+            tracked_entity: None,
+            status: StatusTracker::default(),
+            poise: PoiseTracker::default(),
 
             distance_text: String::new(),
+            poise_text: String::new(),
         }
     }
 
     fn get_data(&self) -> Option<EnemyInfo> {
-        if !self.is_enabled || self.entity_addr == 0 {
+        if !self.is_enabled {
             return None;
         }
 
-        let epc = EntityPointerChains {
-            hp: pointer_chain!(self.entity_addr as usize + 0x190, 0, 0x138),
-            sp: pointer_chain!(self.entity_addr as usize + 0x190, 0, 0x154),
-            mp: pointer_chain!(self.entity_addr as usize + 0x190, 0, 0x148),
-            res: pointer_chain!(self.entity_addr as usize + 0x190, 0x20, 0x10),
-            poise: pointer_chain!(self.entity_addr as usize + 0x190, 0x40, 0x10),
-            position: pointer_chain!(self.entity_addr as usize + 0x190, 0x68, 0x54),
-        };
+        let epc = EntityPointerChains::new(locked_on_entity()?);
 
         let [hp, _, max_hp] = epc.hp.read()?;
         let [sp, _, max_sp] = epc.sp.read()?;
@@ -156,6 +244,68 @@ impl Target {
         Some(EnemyInfo { hp, max_hp, mp, max_mp, sp, max_sp, res, poise, position })
     }
 
+    /// Record the locked-on entity's state for the history views.
+    fn sample(&mut self) {
+        if !self.is_enabled {
+            return;
+        }
+
+        let Some(entity_addr) = locked_on_entity() else {
+            return;
+        };
+
+        let epc = EntityPointerChains::new(entity_addr);
+        if let Some(npc_param_id) = epc.npc_param_id.read() {
+            self.tracker.observe(entity_addr, npc_param_id);
+        }
+
+        if self.tracked_entity != Some(entity_addr) {
+            self.tracked_entity = Some(entity_addr);
+            self.status = StatusTracker::default();
+            self.poise = PoiseTracker::default();
+        }
+
+        let now = Instant::now();
+
+        if let Some(res) = epc.res.read() {
+            self.status.update(&res, now);
+        }
+
+        if let Some(PoiseMeter { poise, poise_max, poise_time, .. }) = epc.poise.read() {
+            self.poise.update(PoiseSample { poise, poise_max, poise_time }, now);
+        }
+    }
+
+    fn render_poise_analysis(&mut self, ui: &imgui::Ui) {
+        self.poise_text.clear();
+        if let Some(damage) = self.poise.last_hit() {
+            write!(self.poise_text, "Last hit {damage:.1}").ok();
+        }
+        if let Some(hits) = self.poise.hits_to_stagger() {
+            write!(self.poise_text, " | {hits} to stagger").ok();
+        }
+        if let Some(countdown) = self.poise.reset_countdown() {
+            write!(self.poise_text, " | reset {countdown:.2}s").ok();
+        }
+        if !self.poise_text.is_empty() {
+            ui.text(&self.poise_text);
+        }
+
+        let now = Instant::now();
+        for stagger in self.poise.staggers().take(3) {
+            self.poise_text.clear();
+            write!(
+                self.poise_text,
+                "Stagger: {} hits, {:.0}+ poise dmg, {:.1}s ago",
+                stagger.hits,
+                stagger.damage,
+                now.duration_since(stagger.at).as_secs_f32()
+            )
+            .ok();
+            ui.text(&self.poise_text);
+        }
+    }
+
     fn enable(&mut self) {
         // Unwraps are valid because the addresses are static.
 
@@ -164,7 +314,7 @@ impl Target {
         let detour_addr = self.detour_addr.eval().unwrap();
         let alloc_addr = self.alloc_addr.eval().unwrap();
 
-        let data_ptr = (&self.entity_addr as *const u64) as usize;
+        let data_ptr = ENTITY_ADDR.as_ptr() as usize;
         let going_jmp_to = (alloc_addr as isize - detour_addr as isize - 5) as i32;
         let returning_jmp_to = (detour_addr as isize - alloc_addr as isize - 11) as i32;
 
@@ -190,6 +340,17 @@ impl Target {
     fn disable(&mut self) {
         self.detour_addr.write(self.detour_orig_data);
         self.is_enabled = false;
+        ENTITY_ADDR.store(0, Ordering::Relaxed);
+    }
+}
+
+// Widgets are rebuilt when the config is reloaded: put the original code back.
+// The trampoline is kept for the next `Target`.
+impl Drop for Target {
+    fn drop(&mut self) {
+        if self.is_enabled {
+            self.disable();
+        }
     }
 }
 
@@ -226,6 +387,16 @@ impl Widget for Target {
                 self.disable();
             }
         }
+
+        if let Some(entity_addr) = locked_on_entity().filter(|_| self.is_enabled) {
+            let edited = self.editor.render(ui, entity_addr);
+            if edited.poise {
+                self.poise = PoiseTracker::default();
+            }
+            if edited.status {
+                self.status = StatusTracker::default();
+            }
+        }
     }
 
     fn render_closed(&mut self, ui: &imgui::Ui) {
@@ -239,6 +410,7 @@ impl Widget for Target {
             if self.is_enabled {
                 ui.text("No enemy locked on")
             };
+            self.tracker.render(ui, None);
             return;
         };
 
@@ -315,6 +487,7 @@ impl Widget for Target {
         let tok = ui.push_style_color(StyleColor::PlotHistogram, COLOR_BASE);
         ProgressBar::new(pct).size(pbar_size).overlay_text("").build(ui);
         drop(tok);
+        self.render_poise_analysis(ui);
 
         pbar("Poison", poison, poison_max, COLOR_POISON);
         pbar("Rot", rot, rot_max, COLOR_ROT);
@@ -324,6 +497,8 @@ impl Widget for Target {
         pbar("Sleep", sleep, sleep_max, COLOR_SLEEP);
         pbar("Mad", mad, mad_max, COLOR_MAD);
 
+        self.status.render(ui);
+
         ui.new_line();
 
         if let Some([x, y, z, _r1, _r2]) = player_chunk_position {
@@ -375,9 +550,13 @@ impl Widget for Target {
             draw_list.add_line([x, y], [x + dx, y + dy], [1.0, 1.0, 1.0]).build();
             ui.new_line();
         }
+
+        self.tracker.render(ui, locked_on_entity());
     }
 
     fn interact(&mut self, ui: &imgui::Ui) {
+        self.sample();
+
         if ui.is_any_item_active() {
             return;
         }
@@ -385,6 +564,16 @@ impl Widget for Target {
         if self.hotkey.map(|k| k.is_pressed(ui)).unwrap_or(false) {
             self.action();
         }
+
+        if self.hotkey_pin.map(|k| k.is_pressed(ui)).unwrap_or(false) {
+            if let Some(entity_addr) = locked_on_entity().filter(|_| self.is_enabled) {
+                if let Some(npc_param_id) =
+                    EntityPointerChains::new(entity_addr).npc_param_id.read()
+                {
+                    self.tracker.toggle_pin(entity_addr, npc_param_id);
+                }
+            }
+        }
     }
 
     fn action(&mut self) {
@@ -0,0 +1,159 @@
+//! Controls for overwriting the locked-on entity's HP, poise and status
+//! buildup, e.g. to practice phase transitions.
+
+use practice_tool_core::widgets::scaling_factor;
+
+use super::{EnemyResistances, EntityPointerChains, PoiseMeter};
+
+const HP_PERCENTAGES: [u32; 5] = [100, 75, 50, 25, 1];
+
+#[derive(Debug, Default)]
+pub(super) struct TargetEditor {
+    hp: i32,
+}
+
+/// Meters overwritten by the controls, whose history shouldn't take the
+/// jump for damage or a proc.
+#[derive(Debug, Default, Clone, Copy)]
+pub(super) struct Edited {
+    pub(super) poise: bool,
+    pub(super) status: bool,
+}
+
+impl TargetEditor {
+    pub(super) fn render(&mut self, ui: &imgui::Ui, entity_addr: u64) -> Edited {
+        let mut edited = Edited::default();
+        let epc = EntityPointerChains::new(entity_addr);
+        let Some([hp, _, max_hp]) = epc.hp.read() else {
+            return edited;
+        };
+
+        let scale = scaling_factor(ui);
+
+        if !ui.is_any_item_active() {
+            self.hp = hp as i32;
+        }
+
+        ui.set_next_item_width(120. * scale);
+        ui.input_int("##target-hp", &mut self.hp).build();
+        ui.same_line();
+        if ui.button("Set HP") {
+            set_hp(&epc, self.hp.max(0) as u32);
+        }
+
+        for pct in HP_PERCENTAGES {
+            if pct != HP_PERCENTAGES[0] {
+                ui.same_line();
+            }
+            if ui.small_button(format!("{pct}%##target-hp-pct")) {
+                set_hp(&epc, hp_percentage(max_hp, pct));
+            }
+        }
+
+        if ui.button("Refill poise") {
+            edited.poise = set_poise(&epc, |poise| poise.poise_max);
+        }
+        ui.same_line();
+        if ui.button("Zero poise") {
+            edited.poise = set_poise(&epc, |_| 0.);
+        }
+
+        if ui.button("Fill status") {
+            edited.status = set_buildup(&epc, fill);
+        }
+        if ui.is_item_hovered() {
+            ui.tooltip_text("Build up every status to one point short of a proc");
+        }
+        ui.same_line();
+        if ui.button("Zero status") {
+            edited.status = set_buildup(&epc, |_, _| 0);
+        }
+
+        edited
+    }
+}
+
+fn hp_percentage(max_hp: u32, pct: u32) -> u32 {
+    (max_hp as u64 * pct as u64 / 100).max(1) as u32
+}
+
+/// Buildup one point short of the maximum, so that the next hit that builds
+/// up the status procs it. Like the meters, this takes buildup to count up
+/// from zero to the maximum; values already past that point are kept.
+fn fill(value: u32, max: u32) -> u32 {
+    value.max(max.saturating_sub(1))
+}
+
+fn set_hp(epc: &EntityPointerChains, hp: u32) {
+    if let Some([_, _, max_hp]) = epc.hp.read() {
+        epc.hp.cast::<u32>().write(hp.min(max_hp));
+    }
+}
+
+fn set_poise(epc: &EntityPointerChains, f: impl FnOnce(&PoiseMeter) -> f32) -> bool {
+    let Some(poise) = epc.poise.read() else {
+        return false;
+    };
+    epc.poise.cast::<f32>().write(f(&poise)).is_some()
+}
+
+/// Write the buildups that `f` changes one by one, leaving the maxima and the
+/// other statuses as the game has them.
+fn set_buildup(epc: &EntityPointerChains, f: impl Fn(u32, u32) -> u32) -> bool {
+    let Some(res) = epc.res.read() else {
+        return false;
+    };
+
+    for (index, value) in buildup_changes(&res, f) {
+        epc.buildup(index).write(value);
+    }
+    true
+}
+
+/// Index, in memory order, and new value of each buildup that `f` changes.
+fn buildup_changes(res: &EnemyResistances, f: impl Fn(u32, u32) -> u32) -> Vec<(usize, u32)> {
+    res.meters()
+        .into_iter()
+        .enumerate()
+        .filter_map(|(index, (value, max))| {
+            Some((index, f(value, max))).filter(|(_, new)| *new != value)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hp_percentage() {
+        assert_eq!(hp_percentage(1000, 100), 1000);
+        assert_eq!(hp_percentage(1000, 25), 250);
+        assert_eq!(hp_percentage(50, 1), 1);
+        assert_eq!(hp_percentage(u32::MAX, 75), (u32::MAX as u64 * 3 / 4) as u32);
+    }
+
+    #[test]
+    fn test_buildup_changes() {
+        let res = EnemyResistances {
+            poison: 100,
+            rot: 0,
+            bleed: 299,
+            blight: 0,
+            frost: 350,
+            sleep: 0,
+            mad: 0,
+            poison_max: 200,
+            rot_max: 300,
+            bleed_max: 300,
+            blight_max: 0,
+            frost_max: 300,
+            sleep_max: 400,
+            mad_max: 500,
+        };
+
+        assert_eq!(buildup_changes(&res, fill), [(0, 199), (1, 299), (5, 399), (6, 499)]);
+        assert_eq!(buildup_changes(&res, |_, _| 0), [(0, 0), (2, 0), (4, 0)]);
+        assert!(buildup_changes(&EnemyResistances::default(), |_, _| 0).is_empty());
+    }
+}
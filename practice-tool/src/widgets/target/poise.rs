@@ -0,0 +1,254 @@
+//! Poise damage and stagger analysis for the locked-on entity.
+//!
+//! The game only exposes the current poise, its maximum and the time left
+//! before it resets to full. Hits show up as decreases; an increase is either
+//! the reset after the timer ran out or the refill that follows a stagger.
+
+use std::collections::VecDeque;
+use std::time::Instant;
+
+const EPSILON: f32 = 0.01;
+// The timer is sampled once per frame, so it may not read exactly zero on the
+// last frame before the reset.
+const RESET_TOLERANCE: f32 = 0.1;
+const AVERAGE_HITS: usize = 5;
+const LOG_LEN: usize = 8;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(super) struct PoiseSample {
+    pub(super) poise: f32,
+    pub(super) poise_max: f32,
+    pub(super) poise_time: f32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(super) enum PoiseEvent {
+    Hit(f32),
+    /// The poise broke. Carries the poise left before the breaking hit, which
+    /// is a lower bound for that hit's poise damage.
+    Stagger(f32),
+    Reset,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(super) struct StaggerEntry {
+    pub(super) at: Instant,
+    pub(super) hits: u32,
+    pub(super) damage: f32,
+}
+
+#[derive(Debug, Default)]
+pub(super) struct PoiseTracker {
+    last: Option<PoiseSample>,
+    recent_hits: VecDeque<f32>,
+    chain_hits: u32,
+    chain_damage: f32,
+    staggers: VecDeque<StaggerEntry>,
+}
+
+impl PoiseTracker {
+    pub(super) fn update(&mut self, sample: PoiseSample, now: Instant) -> Vec<PoiseEvent> {
+        let mut events = Vec::new();
+
+        let Some(last) = self.last.replace(sample) else {
+            return events;
+        };
+
+        // Phase transitions and the like change the maximum. Start over.
+        if (last.poise_max - sample.poise_max).abs() > EPSILON {
+            self.chain_hits = 0;
+            self.chain_damage = 0.;
+            return events;
+        }
+
+        if sample.poise < last.poise - EPSILON {
+            if sample.poise <= EPSILON {
+                events.push(PoiseEvent::Stagger(last.poise));
+            } else {
+                events.push(PoiseEvent::Hit(last.poise - sample.poise));
+            }
+        } else if sample.poise > last.poise + EPSILON {
+            if last.poise <= EPSILON {
+                // Refill after a stagger that was already observed.
+            } else if last.poise_time <= RESET_TOLERANCE {
+                events.push(PoiseEvent::Reset);
+                // A hit landed on the same frame as the reset.
+                if sample.poise < sample.poise_max - EPSILON {
+                    events.push(PoiseEvent::Hit(sample.poise_max - sample.poise));
+                }
+            } else {
+                events.push(PoiseEvent::Stagger(last.poise));
+            }
+        }
+
+        for event in &events {
+            self.apply(*event, now);
+        }
+
+        events
+    }
+
+    fn apply(&mut self, event: PoiseEvent, now: Instant) {
+        match event {
+            PoiseEvent::Hit(damage) => {
+                if self.recent_hits.len() >= AVERAGE_HITS {
+                    self.recent_hits.pop_front();
+                }
+                self.recent_hits.push_back(damage);
+                self.chain_hits += 1;
+                self.chain_damage += damage;
+            },
+            PoiseEvent::Stagger(damage) => {
+                if self.staggers.len() >= LOG_LEN {
+                    self.staggers.pop_back();
+                }
+                self.staggers.push_front(StaggerEntry {
+                    at: now,
+                    hits: self.chain_hits + 1,
+                    damage: self.chain_damage + damage,
+                });
+                self.chain_hits = 0;
+                self.chain_damage = 0.;
+            },
+            PoiseEvent::Reset => {
+                self.chain_hits = 0;
+                self.chain_damage = 0.;
+            },
+        }
+    }
+
+    /// Poise damage of the most recent hit.
+    pub(super) fn last_hit(&self) -> Option<f32> {
+        self.recent_hits.back().copied()
+    }
+
+    /// Hits needed to break the current poise, assuming they deal as much
+    /// poise damage as the recent ones did on average.
+    pub(super) fn hits_to_stagger(&self) -> Option<u32> {
+        let last = self.last?;
+        let average = self.recent_hits.iter().sum::<f32>() / self.recent_hits.len() as f32;
+        if average.is_nan() || average <= EPSILON {
+            return None;
+        }
+        Some((last.poise / average).ceil().max(1.) as u32)
+    }
+
+    /// Seconds until the poise resets to full, if it isn't full already.
+    pub(super) fn reset_countdown(&self) -> Option<f32> {
+        self.last
+            .filter(|last| last.poise < last.poise_max - EPSILON)
+            .map(|last| last.poise_time.max(0.))
+    }
+
+    /// Staggers, most recent first.
+    pub(super) fn staggers(&self) -> impl Iterator<Item = &StaggerEntry> {
+        self.staggers.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(poise: f32, poise_time: f32) -> PoiseSample {
+        PoiseSample { poise, poise_max: 100., poise_time }
+    }
+
+    #[test]
+    fn test_hits() {
+        let now = Instant::now();
+        let mut tracker = PoiseTracker::default();
+
+        assert_eq!(tracker.update(sample(100., 0.), now), []);
+        assert_eq!(tracker.hits_to_stagger(), None);
+        assert_eq!(tracker.reset_countdown(), None);
+
+        assert_eq!(tracker.update(sample(80., 30.), now), [PoiseEvent::Hit(20.)]);
+        assert_eq!(tracker.update(sample(80., 29.5), now), []);
+        assert_eq!(tracker.update(sample(50., 30.), now), [PoiseEvent::Hit(30.)]);
+
+        assert_eq!(tracker.last_hit(), Some(30.));
+        // 50 poise left, 25 per hit on average.
+        assert_eq!(tracker.hits_to_stagger(), Some(2));
+        assert_eq!(tracker.reset_countdown(), Some(30.));
+    }
+
+    #[test]
+    fn test_stagger() {
+        let now = Instant::now();
+        let mut tracker = PoiseTracker::default();
+
+        tracker.update(sample(100., 0.), now);
+        tracker.update(sample(60., 30.), now);
+
+        // Poise refilled while the timer was still running: the hit broke it.
+        assert_eq!(tracker.update(sample(100., 30.), now), [PoiseEvent::Stagger(60.)]);
+
+        let staggers = tracker.staggers().collect::<Vec<_>>();
+        assert_eq!(staggers, [&StaggerEntry { at: now, hits: 2, damage: 100. }]);
+    }
+
+    #[test]
+    fn test_stagger_observed_at_zero() {
+        let now = Instant::now();
+        let mut tracker = PoiseTracker::default();
+
+        tracker.update(sample(100., 0.), now);
+        tracker.update(sample(30., 30.), now);
+        assert_eq!(tracker.update(sample(0., 30.), now), [PoiseEvent::Stagger(30.)]);
+
+        // The refill that follows is not another stagger.
+        assert_eq!(tracker.update(sample(100., 30.), now), []);
+        assert_eq!(tracker.staggers().count(), 1);
+    }
+
+    #[test]
+    fn test_reset_after_timer() {
+        let now = Instant::now();
+        let mut tracker = PoiseTracker::default();
+
+        tracker.update(sample(100., 0.), now);
+        tracker.update(sample(60., 30.), now);
+        tracker.update(sample(60., 0.05), now);
+
+        assert_eq!(tracker.update(sample(100., 0.), now), [PoiseEvent::Reset]);
+        assert_eq!(tracker.staggers().count(), 0);
+        assert_eq!(tracker.reset_countdown(), None);
+
+        // The chain starts over after the reset.
+        tracker.update(sample(50., 30.), now);
+        tracker.update(sample(100., 30.), now);
+        let staggers = tracker.staggers().collect::<Vec<_>>();
+        assert_eq!(staggers, [&StaggerEntry { at: now, hits: 2, damage: 100. }]);
+    }
+
+    #[test]
+    fn test_reset_and_hit_on_same_frame() {
+        let now = Instant::now();
+        let mut tracker = PoiseTracker::default();
+
+        tracker.update(sample(100., 0.), now);
+        tracker.update(sample(90., 30.), now);
+        tracker.update(sample(90., 0.), now);
+
+        assert_eq!(tracker.update(sample(95., 30.), now), [
+            PoiseEvent::Reset,
+            PoiseEvent::Hit(5.)
+        ]);
+        assert_eq!(tracker.last_hit(), Some(5.));
+    }
+
+    #[test]
+    fn test_max_change() {
+        let now = Instant::now();
+        let mut tracker = PoiseTracker::default();
+
+        tracker.update(sample(100., 0.), now);
+        tracker.update(sample(40., 30.), now);
+
+        let phase_two = PoiseSample { poise: 150., poise_max: 150., poise_time: 0. };
+        assert_eq!(tracker.update(phase_two, now), []);
+        assert_eq!(tracker.staggers().count(), 0);
+        assert_eq!(tracker.reset_countdown(), None);
+    }
+}
@@ -0,0 +1,174 @@
+//! Status buildup history for the locked-on entity.
+
+use std::collections::VecDeque;
+use std::fmt::Write;
+use std::time::{Duration, Instant};
+
+use super::EnemyResistances;
+
+const SAMPLE_INTERVAL: Duration = Duration::from_millis(100);
+const HISTORY_LEN: usize = 100;
+
+const STATUS_NAMES: [&str; 7] = ["Poison", "Rot", "Bleed", "Blight", "Frost", "Sleep", "Mad"];
+
+#[derive(Debug, Default)]
+struct StatusHistory {
+    samples: VecDeque<f32>,
+    last: Option<(Instant, u32, u32)>,
+    procs: u32,
+    last_proc: Option<Instant>,
+    decay_rate: f32,
+}
+
+impl StatusHistory {
+    fn update(&mut self, value: u32, max: u32, now: Instant) {
+        if let Some((last_time, last_value, last_max)) = self.last {
+            // A proc empties the meter and raises the resistance.
+            if (last_max > 0 && max > last_max) || last_value.saturating_sub(value) > max / 2 {
+                self.procs += 1;
+                self.last_proc = Some(now);
+            } else if value < last_value {
+                let dt = now.duration_since(last_time).as_secs_f32();
+                if dt > 0. {
+                    self.decay_rate = (last_value - value) as f32 / dt;
+                }
+            }
+        }
+
+        if self.samples.len() >= HISTORY_LEN {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(value as f32);
+        self.last = Some((now, value, max));
+    }
+
+    fn is_active(&self) -> bool {
+        self.procs > 0 || self.samples.iter().any(|&v| v > 0.)
+    }
+}
+
+/// Samples the target's status meters at a fixed rate and keeps a short
+/// history of each, along with proc counts and decay rates.
+#[derive(Debug, Default)]
+pub(super) struct StatusTracker {
+    last_sample: Option<Instant>,
+    statuses: [StatusHistory; 7],
+    text: String,
+}
+
+impl StatusTracker {
+    pub(super) fn update(&mut self, res: &EnemyResistances, now: Instant) {
+        if self.last_sample.is_some_and(|t| now.duration_since(t) < SAMPLE_INTERVAL) {
+            return;
+        }
+        self.last_sample = Some(now);
+
+        for (status, (value, max)) in self.statuses.iter_mut().zip(res.meters()) {
+            status.update(value, max, now);
+        }
+    }
+
+    pub(super) fn render(&mut self, ui: &imgui::Ui) {
+        let now = Instant::now();
+
+        for (name, status) in STATUS_NAMES.iter().zip(self.statuses.iter_mut()) {
+            if !status.is_active() {
+                continue;
+            }
+
+            self.text.clear();
+            write!(self.text, "{name:8} procs {} decay {:.1}/s", status.procs, status.decay_rate)
+                .ok();
+            if let Some(t) = status.last_proc {
+                write!(self.text, " last {:.1}s ago", now.duration_since(t).as_secs_f32()).ok();
+            }
+            ui.text(&self.text);
+
+            let max = status.last.map(|(_, _, max)| max).unwrap_or_default() as f32;
+            ui.plot_lines(format!("##status-history-{name}"), status.samples.make_contiguous())
+                .scale_min(0.)
+                .scale_max(max.max(1.))
+                .graph_size([200., 24.])
+                .build();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(start: Instant, millis: u64) -> Instant {
+        start + Duration::from_millis(millis)
+    }
+
+    #[test]
+    fn test_procs() {
+        let start = Instant::now();
+        let mut history = StatusHistory::default();
+
+        for (i, value) in [0, 30, 60, 90].into_iter().enumerate() {
+            history.update(value, 100, at(start, i as u64 * 100));
+        }
+        assert_eq!(history.procs, 0);
+
+        // The meter empties and the resistance goes up.
+        history.update(0, 120, at(start, 400));
+        assert_eq!((history.procs, history.last_proc), (1, Some(at(start, 400))));
+
+        // The meter empties with the same resistance.
+        history.update(100, 120, at(start, 500));
+        history.update(0, 120, at(start, 600));
+        assert_eq!((history.procs, history.last_proc), (2, Some(at(start, 600))));
+    }
+
+    #[test]
+    fn test_decay() {
+        let start = Instant::now();
+        let mut history = StatusHistory::default();
+
+        history.update(60, 100, start);
+        history.update(50, 100, at(start, 1000));
+        assert_eq!(history.decay_rate, 10.);
+        history.update(45, 100, at(start, 1250));
+        assert_eq!(history.decay_rate, 20.);
+
+        // Building up keeps the last decay rate.
+        history.update(80, 100, at(start, 1500));
+        assert_eq!((history.decay_rate, history.procs), (20., 0));
+    }
+
+    #[test]
+    fn test_history() {
+        let start = Instant::now();
+        let mut history = StatusHistory::default();
+        assert!(!history.is_active());
+
+        history.update(0, 100, start);
+        assert!(!history.is_active());
+
+        for i in 1..=150 {
+            history.update(i % 50, 100, at(start, i as u64));
+        }
+        assert!(history.is_active());
+        assert_eq!(history.samples.len(), HISTORY_LEN);
+        assert_eq!(history.samples.back(), Some(&0.));
+        assert_eq!(history.samples.front(), Some(&1.));
+    }
+
+    #[test]
+    fn test_sample_interval() {
+        let start = Instant::now();
+        let mut tracker = StatusTracker::default();
+        let bleed = |bleed| EnemyResistances { bleed, bleed_max: 100, ..Default::default() };
+
+        tracker.update(&bleed(10), start);
+        tracker.update(&bleed(20), at(start, 50));
+        tracker.update(&bleed(30), at(start, 100));
+        tracker.update(&bleed(40), at(start, 150));
+
+        let samples = |i: usize| tracker.statuses[i].samples.iter().copied().collect::<Vec<_>>();
+        assert_eq!(samples(2), [10., 30.]);
+        assert_eq!(samples(0), [0., 0.]);
+    }
+}
@@ -0,0 +1,169 @@
+//! Compact view of the last few entities that were locked on.
+//!
+//! Entities are remembered by address, which the game reuses once they are
+//! unloaded. Their NPC param id is remembered too and checked before their
+//! state is read, so that a different entity in the same memory is dropped.
+
+use std::collections::VecDeque;
+use std::fmt::Write;
+
+use super::{EntityPointerChains, PoiseMeter};
+
+#[derive(Debug)]
+struct TrackedEntity {
+    addr: u64,
+    npc_param_id: u32,
+    pinned: bool,
+}
+
+/// Remembers up to `capacity` distinct entities, most recent first. Pinned
+/// entities are never evicted.
+#[derive(Debug, Default)]
+pub(super) struct EntityTracker {
+    capacity: usize,
+    entities: VecDeque<TrackedEntity>,
+    text: String,
+}
+
+impl EntityTracker {
+    pub(super) fn new(capacity: usize) -> Self {
+        EntityTracker { capacity, ..Default::default() }
+    }
+
+    pub(super) fn observe(&mut self, addr: u64, npc_param_id: u32) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        let entity = match self.entities.iter().position(|e| e.addr == addr) {
+            Some(i) => {
+                let entity = self.entities.remove(i).unwrap();
+                // Same address, different entity: the old one is gone.
+                let pinned = entity.pinned && entity.npc_param_id == npc_param_id;
+                TrackedEntity { addr, npc_param_id, pinned }
+            },
+            None => TrackedEntity { addr, npc_param_id, pinned: false },
+        };
+        self.entities.push_front(entity);
+
+        while self.entities.len() > self.capacity {
+            match self.entities.iter().rposition(|e| !e.pinned) {
+                Some(i) => self.entities.remove(i),
+                None => break,
+            };
+        }
+    }
+
+    pub(super) fn toggle_pin(&mut self, addr: u64, npc_param_id: u32) {
+        self.observe(addr, npc_param_id);
+        if let Some(entity) = self.entities.iter_mut().find(|e| e.addr == addr) {
+            entity.pinned = !entity.pinned;
+        }
+    }
+
+    /// Forget the entities whose NPC param id can't be read anymore, or isn't
+    /// the one they were observed with.
+    fn retain_valid(&mut self, read_npc_param_id: impl Fn(u64) -> Option<u32>) {
+        self.entities.retain(|e| read_npc_param_id(e.addr) == Some(e.npc_param_id));
+    }
+
+    pub(super) fn render(&mut self, ui: &imgui::Ui, current: Option<u64>) {
+        self.retain_valid(|addr| EntityPointerChains::new(addr).npc_param_id.read());
+
+        if self.entities.len() < 2 && !self.entities.iter().any(|e| e.pinned) {
+            return;
+        }
+
+        ui.separator();
+
+        for entity in &self.entities {
+            let epc = EntityPointerChains::new(entity.addr);
+            let (Some([hp, _, max_hp]), Some(PoiseMeter { poise, poise_max, .. })) =
+                (epc.hp.read(), epc.poise.read())
+            else {
+                continue;
+            };
+
+            self.text.clear();
+            let marker = match (current == Some(entity.addr), entity.pinned) {
+                (true, _) => '>',
+                (false, true) => '*',
+                (false, false) => ' ',
+            };
+            write!(self.text, "{marker} {:<9}", entity.npc_param_id).ok();
+            write!(self.text, " HP {hp:>6}/{max_hp:<6} Poise {poise:>4.0}/{poise_max:<4.0}").ok();
+            ui.text(&self.text);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn addrs(tracker: &EntityTracker) -> Vec<(u64, bool)> {
+        tracker.entities.iter().map(|e| (e.addr, e.pinned)).collect()
+    }
+
+    #[test]
+    fn test_observe() {
+        let mut tracker = EntityTracker::new(3);
+        tracker.observe(1, 100);
+        tracker.observe(2, 200);
+        tracker.observe(1, 100);
+        assert_eq!(addrs(&tracker), [(1, false), (2, false)]);
+
+        // Oldest first out.
+        tracker.observe(3, 300);
+        tracker.observe(4, 400);
+        assert_eq!(addrs(&tracker), [(4, false), (3, false), (1, false)]);
+
+        let mut tracker = EntityTracker::new(0);
+        tracker.observe(1, 100);
+        assert!(tracker.entities.is_empty());
+    }
+
+    #[test]
+    fn test_pin() {
+        let mut tracker = EntityTracker::new(2);
+        tracker.observe(1, 100);
+        tracker.toggle_pin(2, 200);
+        tracker.observe(3, 300);
+        tracker.observe(4, 400);
+        // The pinned entity stays even though it's the oldest.
+        assert_eq!(addrs(&tracker), [(4, false), (2, true)]);
+
+        // Pinned entities take all of the capacity.
+        tracker.toggle_pin(4, 400);
+        tracker.observe(5, 500);
+        assert_eq!(addrs(&tracker), [(4, true), (2, true)]);
+
+        tracker.toggle_pin(2, 200);
+        tracker.observe(5, 500);
+        assert_eq!(addrs(&tracker), [(5, false), (4, true)]);
+
+        // A different entity at a pinned address isn't pinned.
+        tracker.observe(4, 401);
+        assert_eq!(addrs(&tracker), [(4, false), (5, false)]);
+    }
+
+    #[test]
+    fn test_evict() {
+        let mut tracker = EntityTracker::new(4);
+        tracker.toggle_pin(1, 100);
+        tracker.observe(2, 200);
+        tracker.observe(3, 300);
+
+        // 2 was unloaded, and another entity reuses the memory of 3.
+        tracker.retain_valid(|addr| match addr {
+            1 => Some(100),
+            3 => Some(301),
+            _ => None,
+        });
+        assert_eq!(addrs(&tracker), [(1, true)]);
+
+        // Pinned entities are dropped too once they are gone.
+        tracker.retain_valid(|_| None);
+        assert!(tracker.entities.is_empty());
+    }
+}
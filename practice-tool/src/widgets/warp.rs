@@ -58,6 +58,22 @@ impl Warp {
             warp_fn(arg1, arg2, GRACES[self.current_grace].1 - 0x3e8);
         }
     }
+
+    /// Select the grace at `index` in the list and warp to it.
+    pub(crate) fn warp_to_grace(&mut self, index: usize) {
+        self.current_grace = index;
+        self.warp();
+    }
+}
+
+/// Look up a grace by name and return its index in the list. Exact (case
+/// insensitive) matches win over fuzzy ones.
+pub(crate) fn find_grace(name: &str) -> Option<(usize, &'static str)> {
+    GRACES
+        .iter()
+        .position(|(grace, _)| grace.eq_ignore_ascii_case(name))
+        .or_else(|| GRACES.iter().position(|(grace, _)| string_match(name, grace)))
+        .map(|index| (index, GRACES[index].0))
 }
 
 impl Widget for Warp {
@@ -2,6 +2,7 @@ use std::ffi::OsStr;
 use std::{env, fs, iter};
 
 use anyhow::{bail, Context, Result};
+use practice_tool_config::schema;
 use practice_tool_tasks::{
     cargo_command, project_root, steam_command, target_path, Distribution, FileInstall,
 };
@@ -17,6 +18,7 @@ fn main() -> Result<()> {
     match task.as_deref() {
         Some("dist") => dist()?,
         Some("codegen") => codegen::codegen()?,
+        Some("schema") => schema()?,
         Some("inject") => inject(env::args().skip(1))?,
         Some("run") => run()?,
         Some("install") => install()?,
@@ -35,6 +37,7 @@ Tasks:
 run ............. compile and start the practice tool
 dist ............ build distribution artifacts
 codegen ......... generate Rust code: parameters, base addresses, ...
+schema .......... generate the JSON Schema of the config file
 inject <args> ... standalone dll inject
 install ......... install standalone dll to $ER_PATH
 uninstall ....... uninstall standalone dll from $ER_PATH
@@ -71,9 +74,18 @@ fn dist() -> Result<()> {
         .with_artifact("jdsd_er_practice_tool.exe", "jdsd_er_practice_tool.exe")
         .with_file("lib/data/RELEASE-README.txt", "README.txt")
         .with_file("jdsd_er_practice_tool.toml", "jdsd_er_practice_tool.toml")
+        .with_file("jdsd_er_practice_tool.schema.json", "jdsd_er_practice_tool.schema.json")
         .build(&["--locked", "--release", "--workspace", "--exclude", "xtask"])
 }
 
+fn schema() -> Result<()> {
+    let path = project_root().join(schema::SCHEMA_FILE);
+    fs::write(&path, schema::schema_string()).with_context(|| format!("{}", path.display()))?;
+    println!("Wrote {}", path.display());
+
+    Ok(())
+}
+
 fn install() -> Result<()> {
     let status = cargo_command("build")
         .args(["--lib", "--release", "--package", "eldenring-practice-tool"])